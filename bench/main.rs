@@ -2,6 +2,7 @@ mod utils;
 
 use criterion::*;
 use sahomedb::prelude::*;
+use sahomedb::simd::Kernel;
 use utils::*;
 
 fn build_collection(path: &str) -> Collection {
@@ -33,5 +34,31 @@ fn bench_search_collection(criterion: &mut Criterion) {
     criterion.bench_function(id, |bencher| bencher.iter(routine));
 }
 
-criterion_group!(bench, bench_search_collection);
+fn bench_distance_kernels(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("Distance kernels");
+
+    // The dimension of the SIFT vectors.
+    let a = Vector::random(128);
+    let b = Vector::random(128);
+
+    // Benchmark each kernel supported by the CPU.
+    for kernel in Kernel::available() {
+        let name = kernel.name();
+        group.bench_function(BenchmarkId::new("dot", name), |bencher| {
+            bencher.iter(|| black_box(kernel.dot(&a.0, &b.0)))
+        });
+
+        group.bench_function(BenchmarkId::new("euclidean", name), |bencher| {
+            bencher.iter(|| black_box(kernel.squared(&a.0, &b.0).sqrt()))
+        });
+
+        group.bench_function(BenchmarkId::new("cosine", name), |bencher| {
+            bencher.iter(|| black_box(kernel.cosine(&a.0, &b.0)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(bench, bench_distance_kernels, bench_search_collection);
 criterion_main!(bench);
@@ -76,23 +76,17 @@ pub fn read_vectors(path: &str) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
     let n = reader.seek(SeekFrom::End(0))? as usize / vector_size;
 
     // Seek the starting position.
-    reader.seek(SeekFrom::Start(((0) * vector_size) as u64))?;
-
-    // Read the vectors.
-    let mut _vectors = vec![vec![0f32; n]; dimension];
-    for i in 0..n {
-        for j in 0..dimension {
-            _vectors[j][i] = reader.read_f32::<LittleEndian>()?;
-        }
+    reader.seek(SeekFrom::Start(0))?;
+
+    // Read the vectors. Each vector is prefixed by its dimension.
+    let mut vectors = Vec::with_capacity(n);
+    for _ in 0..n {
+        reader.read_i32::<LittleEndian>()?;
+        let mut vector = vec![0f32; dimension];
+        reader.read_f32_into::<LittleEndian>(&mut vector)?;
+        vectors.push(vector);
     }
 
-    // Transpose the vector.
-    let rows = _vectors.len();
-    let cols = _vectors[0].len();
-    let vectors = (0..cols)
-        .map(|col| (0..rows).map(|row| _vectors[row][col]).collect())
-        .collect();
-
     Ok(vectors)
 }
 
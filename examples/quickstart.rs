@@ -7,10 +7,8 @@ fn main() {
     // Replace with your own data.
     let records = Record::many_random(dimension, 100);
 
-    let mut config = Config::default();
-
     // Optionally set the distance function. Default to Euclidean.
-    config.distance = Distance::Cosine;
+    let config = Config { distance: Distance::Cosine, ..Default::default() };
 
     // Create a vector collection.
     let collection = Collection::build(&config, &records).unwrap();
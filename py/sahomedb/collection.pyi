@@ -1,7 +1,7 @@
 # flake8: noqa F821
 
-from typing import Any, List, Dict
-from sahomedb.vector import Vector, VectorID
+from typing import Any, Callable, List, Dict, Optional, Union
+from sahomedb.vector import SparseVector, Vector, VectorID
 
 
 class Config:
@@ -11,13 +11,42 @@ class Config:
     - ef_construction: Nodes to consider during index construction.
     - ef_search: Nodes to consider during the search.
     - ml: Layer multiplier of the HNSW index.
-    - distance: Distance metric function.
+    - distance: Distance metric function: dot, euclidean, cosine,
+      hamming, manhattan, chebyshev, squared_euclidean, or jaccard.
+      With cosine, the vectors are stored normalized and the
+      original vectors are restored when retrieved.
+
+    Attributes:
+    - m: Max number of neighbors of a node in the upper layers.
+      Higher values improve the recall at the cost of memory.
+    - m0: Max number of neighbors of a node in the base layer.
+      It should be at least m, usually 2 * m.
+    - extend_candidates: Consider the neighbors of the candidates
+      when selecting the neighbors of a new node.
+    - keep_pruned_connections: Fill the free neighbor slots with
+      the candidates pruned by the selection heuristic.
+    - compaction_threshold: Ratio of deleted records at which
+      the collection compacts itself. None to disable.
+    - quantization: Quantization of the stored vectors: scalar,
+      scalar_per_vector, product, binary, or None to store full
+      precision vectors.
+    - rerank: Re-rank the search results with the full precision
+      vectors. They're kept in memory alongside the quantized ones.
+    - subvectors: Number of sub-vectors for product quantization.
     """
 
     ef_construction: int
     ef_search: int
     ml: float
+    m: int
+    m0: int
+    extend_candidates: bool
+    keep_pruned_connections: bool
     distance: str
+    compaction_threshold: Optional[float]
+    quantization: Optional[str]
+    rerank: bool
+    subvectors: int
 
     def __init__(
         self,
@@ -35,7 +64,15 @@ class Config:
         - ef_construction: 40
         - ef_search: 15
         - ml: 0.3
+        - m: 32
+        - m0: 64
+        - extend_candidates: False
+        - keep_pruned_connections: False
         - distance: euclidean
+        - compaction_threshold: None
+        - quantization: None
+        - rerank: False
+        - subvectors: 8
         """
 
 
@@ -45,6 +82,9 @@ class Record:
     Args:
     - vector: Vector embedding of float values.
     - data: Metadata of the vector.
+    - key: Optional string or integer key to address the record.
+    - sparse: Optional sparse vector like keyword weights
+      for the sparse and hybrid search.
 
     Metadata types:
     - String
@@ -55,8 +95,16 @@ class Record:
 
     vector: Vector
     data: Any
+    key: Optional[Union[str, int]]
+    sparse: Optional[SparseVector]
 
-    def __init__(self, vector: List[float], data: Any) -> None: ...
+    def __init__(
+        self,
+        vector: List[float],
+        data: Any,
+        key: Optional[Union[str, int]] = None,
+        sparse: Optional[SparseVector] = None,
+    ) -> None: ...
 
     @staticmethod
     def random(dimension: int) -> Record:
@@ -83,6 +131,7 @@ class Collection:
     config: Config
     dimension: int
     relevancy: float
+    indexes: Dict[str, str]
 
     def __init__(self, config: Config) -> None: ...
 
@@ -120,13 +169,62 @@ class Collection:
         - records: Records to insert.
         """
 
-    def delete(self, id: VectorID) -> None:
-        """Deletes a record from the collection.
+    def delete(self, id: VectorID) -> bool:
+        """Deletes a record from the collection. Returns
+        true if the collection compacted itself after the deletion.
 
         Args:
         - id: Vector ID to delete.
         """
 
+    def get_by_key(self, key: Union[str, int]) -> Record:
+        """Returns the record associated with the key.
+
+        Args:
+        - key: Key of the record.
+        """
+
+    def delete_by_key(self, key: Union[str, int]) -> bool:
+        """Deletes the record associated with the key. Returns
+        true if the collection compacted itself after the deletion.
+
+        Args:
+        - key: Key of the record.
+        """
+
+    def upsert(self, record: Record) -> UpsertResult:
+        """Inserts the record if its key is new or updates the
+        record with the same key. Returns which of them happened.
+
+        Args:
+        - record: Record with a key.
+        """
+
+    def upsert_many(self, records: List[Record]) -> List[UpsertResult]:
+        """Inserts the records with new keys and updates the records
+        with existing keys. Returns the result for each record.
+
+        Args:
+        - records: Records with unique keys.
+        """
+
+    def compact(self, remap: bool = False) -> Dict[VectorID, VectorID]:
+        """Rebuilds the index over the remaining records to reclaim
+        the memory of the deleted records. Returns the mapping of
+        the old vector IDs to the new ones.
+
+        Args:
+        - remap: Assign consecutive vector IDs to the records.
+        """
+
+    def train(self) -> None:
+        """Trains the product quantizer or fits the scalar quantizer
+        on the stored vectors and quantizes them. Collections built
+        from records are trained automatically, and the scalar
+        quantizer is fitted once 1000 vectors are inserted. Does
+        nothing for other quantizations.
+        """
+
     def get(self, id: VectorID) -> Record:
         """Returns a record from the collection.
 
@@ -140,30 +238,122 @@ class Collection:
         """
 
     def update(self, id: VectorID, record: Record) -> None:
-        """Updates a record in the collection.
+        """Updates a record in the collection. The sparse vector
+        is replaced along with the vector.
 
         Args:
         - id: Vector ID to update.
         - record: New record.
         """
 
-    def search(self, vector: Vector, n: int) -> List[SearchResult]:
+    def search(
+        self,
+        vector: Vector,
+        n: int,
+        params: Optional[SearchParams] = None,
+    ) -> List[SearchResult]:
         """Searches for the nearest neighbors to
         the given vector using HNSW indexing algorithm
 
         Args:
         - vector: Vector to search.
         - n: Number of neighbors to return.
+        - params: Parameters overriding the configuration
+          for this search only.
+        """
+
+    def search_with_filter(
+        self,
+        vector: Vector,
+        n: int,
+        filter: Union[str, Dict[str, Any]],
+    ) -> List[SearchResult]:
+        """Searches for the nearest neighbors whose metadata
+        matches the filter using HNSW indexing algorithm.
+
+        Filters can be a string expression like
+        `author.name == "Alice" and year >= 2000` or a dictionary
+        like {"eq": {"author.name": "Alice"}, "gte": {"year": 2000}}.
+
+        Operators: and, or, not, eq, ne, gt, lt, gte, lte, in,
+        contains, and exists.
+
+        Args:
+        - vector: Vector to search.
+        - n: Number of neighbors to return.
+        - filter: Metadata filter the results must match.
         """
 
-    def true_search(self, vector: Vector, n: int) -> List[SearchResult]:
+    def true_search(
+        self,
+        vector: Vector,
+        n: int,
+        distance: Optional[Callable[[List[float], List[float]], float]] = None,
+    ) -> List[SearchResult]:
         """Searches for the nearest neighbors using brute force.
 
         Args:
         - vector: Vector to search.
         - n: Number of neighbors to return.
+        - distance: Function returning the distance between two
+          vectors to use instead of the configured one. Lower values
+          are closer and the relevancy is not applied.
+        """
+
+    def sparse_search(
+        self,
+        sparse: SparseVector,
+        n: int,
+    ) -> List[SearchResult]:
+        """Searches for the records whose sparse vectors have the
+        highest dot product with the query. The distance of the
+        results is the dot product.
+
+        Args:
+        - sparse: Sparse vector to search.
+        - n: Number of records to return.
+        """
+
+    def hybrid_search(
+        self,
+        vector: Vector,
+        sparse: SparseVector,
+        n: int,
+        fusion: str = "rrf",
+        alpha: float = 0.5,
+        k: float = 60.0,
+    ) -> List[SearchResult]:
+        """Searches with both the dense and the sparse vector and
+        combines their results. The distance of the results is the
+        fused score sorted from the highest.
+
+        Args:
+        - vector: Dense vector to search.
+        - sparse: Sparse vector to search.
+        - n: Number of records to return.
+        - fusion: Method to combine the results: rrf for reciprocal
+          rank fusion or weighted for the weighted sum of the scores.
+        - alpha: Weight of the dense score for the weighted sum.
+          The sparse score is weighted by 1 - alpha.
+        - k: Rank constant of the reciprocal rank fusion.
         """
     
+    def create_index(self, path: str, kind: str) -> None:
+        """Creates an index on a metadata field to speed up
+        filtered searches. An existing index is replaced.
+
+        Args:
+        - path: Dotted path to the metadata field.
+        - kind: Type of the values: keyword, integer, or float.
+        """
+
+    def delete_index(self, path: str) -> None:
+        """Deletes the index of a metadata field.
+
+        Args:
+        - path: Dotted path of the indexed metadata field.
+        """
+
     def len(self) -> int:
         """Returns the number of records in the collection."""
 
@@ -174,9 +364,49 @@ class Collection:
         """Returns True if the vector ID is in the collection."""
 
 
+class SearchParams:
+    """The parameters of a search that override the configuration
+    of the collection for the search only.
+
+    Args:
+    - ef_search: Nodes to consider in the base layer
+      instead of the configured ef_search.
+    - ef_upper: Nodes to consider in the upper layers. Default: 5.
+    - relevancy: Relevancy to use instead of the collection relevancy.
+    - include_vectors: Include the vectors of the records in the results.
+    """
+
+    ef_search: Optional[int]
+    ef_upper: Optional[int]
+    relevancy: Optional[float]
+    include_vectors: bool
+
+    def __init__(
+        self,
+        ef_search: Optional[int] = None,
+        ef_upper: Optional[int] = None,
+        relevancy: Optional[float] = None,
+        include_vectors: bool = False,
+    ) -> None: ...
+
+
 class SearchResult:
-    """The result of a search operation on the collection."""
+    """The result of a search operation on the collection.
+    The distance is the similarity score for the dot and cosine
+    functions. The results are sorted from the most similar.
+    It's the fused score for the hybrid search. The vector is
+    only included if requested by the search parameters.
+    """
 
     id: int
     distance: float
-    data: Any
\ No newline at end of file
+    data: Any
+    key: Optional[Union[str, int]]
+    vector: Optional[Vector]
+
+
+class UpsertResult:
+    """The result of upserting a record to the collection."""
+
+    id: VectorID
+    inserted: bool
@@ -1,6 +1,15 @@
 # flake8: noqa F821
 
-from sahomedb.collection import Collection, Record, Config
+from typing import Dict, List, Optional, Union
+from sahomedb.collection import (
+    Collection,
+    Config,
+    Record,
+    SearchParams,
+    SearchResult,
+    UpsertResult,
+)
+from sahomedb.vector import SparseVector, Vector, VectorID
 
 
 class Database:
@@ -20,6 +29,14 @@ class Database:
         - path: Path to the database file.
         """
 
+    def collection(self, name: str) -> CollectionHandle:
+        """Returns a live handle of the collection. Changes made
+        through the handle are saved to the database automatically.
+
+        Args:
+        - name: Collection name.
+        """
+
     def get_collection(self, name: str) -> Collection:
         """Returns the collection with the given name.
 
@@ -29,6 +46,8 @@ class Database:
 
     def save_collection(self, name: str, collection: Collection) -> None:
         """Saves new or update existing collection to the database.
+        If the collection was loaded from or saved to the database
+        with the same name, only its changes are written.
 
         Args:
         - name: Collection name.
@@ -46,4 +65,181 @@ class Database:
         """Returns the number of collections in the database."""
 
     def is_empty(self) -> bool:
-        """Returns True if the database is empty."""
\ No newline at end of file
+        """Returns True if the database is empty."""
+
+class CollectionHandle:
+    """The live handle of a collection stored in the database.
+    Changes made through the handle are saved immediately. If a
+    change fails to save, it's discarded and an error is raised.
+    """
+
+    @property
+    def name(self) -> str:
+        """Returns the name of the collection."""
+
+    def insert(self, record: Record) -> None:
+        """Inserts a vector record into the collection.
+
+        Args:
+        - record: Vector record to insert.
+        """
+
+    def insert_many(self, records: List[Record]) -> List[VectorID]:
+        """Inserts multiple vector records into the collection.
+
+        Args:
+        - records: List of vector records to insert.
+        """
+
+    def delete(self, id: VectorID) -> bool:
+        """Deletes a vector record from the collection. Returns
+        true if the collection compacted itself after the deletion.
+
+        Args:
+        - id: Vector ID to delete.
+        """
+
+    def update(self, id: VectorID, record: Record) -> None:
+        """Updates a vector record in the collection.
+
+        Args:
+        - id: Vector ID to update.
+        - record: New vector record.
+        """
+
+    def get(self, id: VectorID) -> Record:
+        """Returns the vector record associated with the ID.
+
+        Args:
+        - id: Vector ID to retrieve.
+        """
+
+    def get_by_key(self, key: Union[str, int]) -> Record:
+        """Returns the vector record associated with the key.
+
+        Args:
+        - key: Key of the record.
+        """
+
+    def delete_by_key(self, key: Union[str, int]) -> bool:
+        """Deletes the vector record associated with the key. Returns
+        true if the collection compacted itself after the deletion.
+
+        Args:
+        - key: Key of the record.
+        """
+
+    def upsert(self, record: Record) -> UpsertResult:
+        """Inserts the record if its key is new or updates the
+        record with the same key. Returns which of them happened.
+
+        Args:
+        - record: Vector record with a key.
+        """
+
+    def upsert_many(self, records: List[Record]) -> List[UpsertResult]:
+        """Inserts the records with new keys and updates the records
+        with existing keys. Returns the result for each record.
+
+        Args:
+        - records: Vector records with unique keys.
+        """
+
+    def list(self) -> Dict[VectorID, Record]:
+        """Returns a dictionary of vector records in the collection."""
+
+    def search(
+        self,
+        vector: Vector,
+        n: int,
+        params: Optional[SearchParams] = None,
+    ) -> List[SearchResult]:
+        """Searches the collection for the nearest neighbors.
+
+        Args:
+        - vector: Vector to search.
+        - n: Number of neighbors to return.
+        - params: Parameters overriding the configuration
+          for this search only.
+        """
+
+    def search_with_filter(
+        self,
+        vector: Vector,
+        n: int,
+        filter: Union[str, dict],
+    ) -> List[SearchResult]:
+        """Searches the collection for the nearest neighbors
+        whose metadata matches the filter.
+
+        Args:
+        - vector: Vector to search.
+        - n: Number of neighbors to return.
+        - filter: Filter expression or dictionary.
+        """
+
+    def true_search(self, vector: Vector, n: int) -> List[SearchResult]:
+        """Searches the collection for the true nearest neighbors.
+
+        Args:
+        - vector: Vector to search.
+        - n: Number of neighbors to return.
+        """
+
+    def sparse_search(
+        self,
+        sparse: SparseVector,
+        n: int,
+    ) -> List[SearchResult]:
+        """Searches the collection for the records whose sparse
+        vectors have the highest dot product with the query.
+
+        Args:
+        - sparse: Sparse vector to search.
+        - n: Number of records to return.
+        """
+
+    def hybrid_search(
+        self,
+        vector: Vector,
+        sparse: SparseVector,
+        n: int,
+        fusion: str = "rrf",
+        alpha: float = 0.5,
+        k: float = 60.0,
+    ) -> List[SearchResult]:
+        """Searches the collection with both the dense and the
+        sparse vector and combines their results.
+
+        Args:
+        - vector: Dense vector to search.
+        - sparse: Sparse vector to search.
+        - n: Number of records to return.
+        - fusion: Method to combine the results: rrf or weighted.
+        - alpha: Weight of the dense score for the weighted sum.
+        - k: Rank constant of the reciprocal rank fusion.
+        """
+
+    def compact(self, remap: bool = False) -> Dict[VectorID, VectorID]:
+        """Rebuilds the index over the remaining records and saves
+        the collection. Returns the mapping of old to new vector IDs.
+
+        Args:
+        - remap: Assign consecutive vector IDs to the records.
+        """
+
+    def snapshot(self) -> Collection:
+        """Returns a detached copy of the collection."""
+
+    def contains(self, id: VectorID) -> bool:
+        """Checks if the collection contains the vector ID.
+
+        Args:
+        - id: Vector ID to check.
+        """
+
+    def len(self) -> int:
+        """Returns the number of records in the collection."""
+
+    def is_empty(self) -> bool:
+        """Returns True if the collection is empty."""
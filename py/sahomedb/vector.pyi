@@ -22,6 +22,30 @@ class Vector:
         """Generates a random vector of the given dimension."""
 
 
+class SparseVector:
+    """The sparse vector of non-zero values by their dimension
+    index like SPLADE or BM25 term weights.
+
+    Args:
+    - indices: Unique dimension indices of the values.
+    - values: Values of the dimensions.
+    """
+
+    indices: List[int]
+    values: List[float]
+
+    def __init__(self, indices: List[int], values: List[float]) -> None: ...
+
+    def len(self) -> int:
+        """Returns the number of non-zero values."""
+
+    def is_empty(self) -> bool:
+        """Returns True if the vector has no values."""
+
+    def dot(self, other: SparseVector) -> float:
+        """Returns the dot product with the other sparse vector."""
+
+
 class VectorID:
     """Identifier of the vector in the collection."""
 
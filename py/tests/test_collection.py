@@ -1,4 +1,12 @@
-from sahomedb.prelude import Config, Record, Collection, Vector, VectorID
+from sahomedb.prelude import (
+    Config,
+    Record,
+    Collection,
+    SearchParams,
+    SparseVector,
+    Vector,
+    VectorID,
+)
 
 DIMENSION = 128
 LEN = 100
@@ -31,6 +39,35 @@ def test_create_config():
     assert config.distance == default.distance
 
 
+def test_config_connectivity():
+    config = Config.create_default()
+    config.m = 8
+    config.m0 = 16
+    config.extend_candidates = True
+    config.keep_pruned_connections = True
+
+    records = Record.many_random(dimension=DIMENSION, len=LEN)
+    collection = Collection.from_records(config=config, records=records)
+    assert collection.config.m == 8
+    assert collection.config.m0 == 16
+    assert collection.config.keep_pruned_connections
+
+    results = collection.search(records[0].vector, n=1)
+    assert results[0].id == 0
+
+
+def test_config_distances():
+    config = Config.create_default()
+    distances = ["manhattan", "chebyshev", "squared_euclidean", "jaccard"]
+    for distance in distances:
+        config.distance = distance
+        assert config.distance == distance
+
+        collection = Collection(config=config)
+        collection.insert(Record.random(dimension=DIMENSION))
+        assert len(collection.true_search(Vector.random(DIMENSION), n=1)) == 1
+
+
 def test_create_record():
     vector = [0.1, 0.2, 0.3]
     data = {"text": "This is an example."}
@@ -153,6 +190,224 @@ def test_search_record():
     assert true_results[-1].distance <= collection.relevancy
 
 
+def test_search_with_params():
+    collection = create_test_collection()
+    vector = collection.get(VectorID(0)).vector
+
+    params = SearchParams(ef_search=50, ef_upper=10, include_vectors=True)
+    results = collection.search(vector, n=10, params=params)
+    assert len(results) == 10
+    assert results[0].id == 0
+    assert results[0].vector.to_list() == vector.to_list()
+    assert collection.config.ef_search == 15
+
+    # The relevancy only applies to this search.
+    params = SearchParams(relevancy=0.0)
+    results = collection.search(vector, n=10, params=params)
+    assert [result.id for result in results] == [0]
+    assert collection.relevancy == -1.0
+    assert collection.search(vector, n=10)[0].vector is None
+
+
+def test_search_with_similarity():
+    config = Config.create_default()
+    config.distance = "dot"
+
+    records = Record.many_random(dimension=DIMENSION, len=LEN)
+    collection = Collection.from_records(config=config, records=records)
+
+    # The most similar records have the highest scores.
+    vector = Vector.random(dimension=DIMENSION)
+    results = collection.true_search(vector, n=10)
+    distances = [result.distance for result in results]
+    assert distances == sorted(distances, reverse=True)
+
+
+def test_true_search_with_callable():
+    collection = create_test_collection()
+    vector = Vector.random(dimension=DIMENSION)
+
+    def manhattan(a, b):
+        return sum(abs(x - y) for x, y in zip(a, b))
+
+    results = collection.true_search(vector, n=5, distance=manhattan)
+    assert len(results) == 5
+
+    # The results are sorted by the custom distance.
+    distances = [result.distance for result in results]
+    assert distances == sorted(distances)
+
+    record = collection.get(VectorID(results[0].id))
+    expected = manhattan(vector.to_list(), record.vector.to_list())
+    assert abs(results[0].distance - expected) < 1e-3
+
+    # Errors raised by the function are raised by the search.
+    def invalid(a, b):
+        return "invalid"
+
+    try:
+        collection.true_search(vector, n=5, distance=invalid)
+        assert False
+    except Exception as e:
+        assert "typeerror" in str(e).lower()
+
+
+def test_hybrid_search():
+    records = Record.many_random(dimension=DIMENSION, len=LEN)
+    for i, record in enumerate(records):
+        record.sparse = SparseVector(indices=[i % 10], values=[1.0])
+
+    config = Config.create_default()
+    collection = Collection.from_records(config=config, records=records)
+    assert collection.get(VectorID(3)).sparse.indices == [3]
+
+    sparse = SparseVector(indices=[3], values=[1.0])
+    results = collection.sparse_search(sparse, n=5)
+    assert all(result.id % 10 == 3 for result in results)
+
+    vector = records[0].vector
+    for fusion in ["rrf", "weighted"]:
+        results = collection.hybrid_search(vector, sparse, n=5, fusion=fusion)
+        distances = [result.distance for result in results]
+        assert distances == sorted(distances, reverse=True)
+
+    try:
+        collection.hybrid_search(vector, sparse, n=5, fusion="invalid")
+        assert False
+    except Exception as e:
+        assert "not supported" in str(e).lower()
+
+
+def test_record_key():
+    collection = create_test_collection()
+    record = Record(vector=[0.0] * DIMENSION, data={"a": 1}, key="doc")
+    collection.insert(record)
+
+    assert collection.get_by_key("doc").key == "doc"
+    assert collection.search(record.vector, 1)[0].key == "doc"
+
+    # Upsert updates the record with the same key.
+    record.data = {"a": 2}
+    result = collection.upsert(record)
+    assert not result.inserted
+    assert collection.len() == LEN + 1
+    assert collection.get_by_key("doc").data == {"a": 2}
+
+    collection.delete_by_key("doc")
+    assert collection.len() == LEN
+
+
+def test_upsert_many():
+    collection = create_test_collection()
+    records = Record.many_random(dimension=DIMENSION, len=10)
+    for i, record in enumerate(records):
+        record.key = i
+
+    results = collection.upsert_many(records)
+    assert all(result.inserted for result in results)
+
+    results = collection.upsert_many(records)
+    assert not any(result.inserted for result in results)
+    assert collection.len() == LEN + 10
+
+
+def test_compact():
+    collection = create_test_collection()
+    for i in range(10):
+        # The collection doesn't compact itself without a threshold.
+        assert not collection.delete(VectorID(i))
+
+    mapping = collection.compact(remap=True)
+    assert len(mapping) == LEN - 10
+    assert collection.len() == LEN - 10
+    assert collection.contains(VectorID(0))
+
+
+def test_quantization():
+    config = Config.create_default()
+    config.quantization = "scalar"
+    config.rerank = True
+
+    records = Record.many_random(dimension=DIMENSION, len=LEN)
+    collection = Collection.from_records(config=config, records=records)
+    assert collection.config.quantization == "scalar"
+
+    vector = records[0].vector
+    results = collection.search(vector, n=5)
+    assert results[0].id == 0
+    assert results[0].distance == 0.0
+
+
+def test_product_quantization():
+    config = Config.create_default()
+    config.quantization = "product"
+    config.subvectors = 16
+
+    collection = Collection(config=config)
+    collection.insert_many(Record.many_random(dimension=DIMENSION, len=LEN))
+    collection.train()
+
+    vector = collection.get(VectorID(0)).vector
+    results = collection.search(vector, n=5)
+    assert results[0].id == 0
+
+
+def test_binary_quantization():
+    config = Config.create_default()
+    config.distance = "hamming"
+    config.quantization = "binary"
+    assert config.distance == "hamming"
+    assert config.quantization == "binary"
+
+    records = Record.many_random(dimension=DIMENSION, len=LEN)
+    collection = Collection.from_records(config=config, records=records)
+
+    # The vectors are decoded from the sign bits.
+    vector = collection.get(VectorID(0)).vector
+    assert all(value in [-1.0, 1.0] for value in vector.to_list())
+
+    results = collection.true_search(records[0].vector, n=1)
+    assert results[0].distance == 0.0
+
+
+def test_search_with_filter():
+    config = Config.create_default()
+    records = [
+        Record(vector=Vector.random(DIMENSION).to_list(), data={"tenant": i})
+        for i in [0, 1, 2] * LEN
+    ]
+
+    collection = Collection.from_records(config=config, records=records)
+    vector = Vector.random(dimension=DIMENSION)
+
+    # Filters can be passed as a string or a dictionary.
+    filters = ["tenant == 1", {"eq": {"tenant": 1}}]
+    for filter in filters:
+        results = collection.search_with_filter(vector, n=5, filter=filter)
+        assert len(results) == 5
+        assert all(result.data["tenant"] == 1 for result in results)
+
+
+def test_search_with_invalid_filter():
+    collection = create_test_collection()
+    vector = Vector.random(dimension=DIMENSION)
+
+    try:
+        collection.search_with_filter(vector, n=5, filter="tenant ==")
+        assert False
+    except Exception as e:
+        assert "invalid filter" in str(e).lower()
+
+
+def test_create_index():
+    collection = create_test_collection()
+    collection.create_index(path="tenant", kind="keyword")
+    assert collection.indexes == {"tenant": "keyword"}
+
+    collection.delete_index(path="tenant")
+    assert collection.indexes == {}
+
+
 def test_set_dimension():
     config = Config.create_default()
     collection = Collection(config=config)
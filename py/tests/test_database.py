@@ -55,4 +55,14 @@ def test_save_collection():
 def test_delete_collection():
     db = create_test_database(path="data/105")
     db.delete_collection(name=NAME)
-    assert db.is_empty()
\ No newline at end of file
+    assert db.is_empty()
+
+def test_collection_handle():
+    db = create_test_database(path="data/106")
+    handle = db.collection(name=NAME)
+
+    # Changes through the handle are saved automatically.
+    record = Record.random(dimension=DIMENSION)
+    handle.insert(record)
+    assert handle.len() == LEN + 1
+    assert db.get_collection(name=NAME).len() == LEN + 1
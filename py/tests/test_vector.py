@@ -1,4 +1,4 @@
-from sahomedb.prelude import Vector, VectorID
+from sahomedb.prelude import SparseVector, Vector, VectorID
 
 
 def test_create_vector():
@@ -13,6 +13,21 @@ def test_generate_random_vector():
     assert len(vector) == dimension
 
 
+def test_create_sparse_vector():
+    vector = SparseVector(indices=[7, 3], values=[0.7, 0.3])
+    assert len(vector) == 2
+    assert vector.indices == [3, 7]
+
+    other = SparseVector(indices=[3], values=[2.0])
+    assert abs(vector.dot(other) - 0.6) < 1e-6
+
+    try:
+        SparseVector(indices=[1, 1], values=[0.1, 0.2])
+        assert False
+    except Exception as e:
+        assert "unique" in str(e).lower()
+
+
 def test_create_vector_id():
     id = 1
     vector_id = VectorID(id)
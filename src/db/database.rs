@@ -5,8 +5,13 @@ use super::*;
 pub struct Database {
     collections: Db,
     count: usize,
+    path: String,
+    handles: Mutex<HashMap<String, SharedCollection>>,
 }
 
+/// The collection shared by the handles. None if it's deleted.
+type SharedCollection = Arc<RwLock<Option<Collection>>>;
+
 #[pymethods]
 impl Database {
     /// Re-creates and opens the database at the given path.
@@ -23,7 +28,7 @@ impl Database {
         // with collection's Config.
         let config = sled::Config::new().path(path);
         let collections = config.open()?;
-        Ok(Self { collections, count: 0 })
+        Ok(Self::from_db(collections, path))
     }
 
     /// Opens existing or creates new database.
@@ -32,21 +37,43 @@ impl Database {
     #[new]
     pub fn open(path: &str) -> Result<Self, Error> {
         let collections = sled::open(path)?;
-        let count = collections.len();
-        Ok(Self { collections, count })
+        Ok(Self::from_db(collections, path))
+    }
+
+    /// Returns a live handle of a collection in the database.
+    /// Changes made through the handle are saved automatically
+    /// and are visible to all handles of the collection.
+    /// * `name` - Name of the collection.
+    pub fn collection(&self, name: &str) -> Result<CollectionHandle, Error> {
+        let mut handles = self.handles.lock();
+
+        let collection = match handles.get(name) {
+            Some(collection) => collection.clone(),
+            None => {
+                let collection = self.get_collection(name)?;
+                let shared = Arc::new(RwLock::new(Some(collection)));
+                handles.insert(name.to_string(), shared.clone());
+                shared
+            }
+        };
+
+        Ok(CollectionHandle {
+            name: name.to_string(),
+            path: self.path.clone(),
+            db: self.collections.clone(),
+            collection,
+        })
     }
 
     /// Gets a collection from the database.
     /// * `name` - Name of the collection.
     pub fn get_collection(&self, name: &str) -> Result<Collection, Error> {
-        let value = self.collections.get(name)?;
-        match value {
-            Some(value) => Ok(bincode::deserialize(&value)?),
-            None => Err(Error::collection_not_found()),
-        }
+        storage::load(&self.collections, &self.path, name)
     }
 
     /// Saves new or update existing collection to the database.
+    /// If the collection was loaded from or saved to the database
+    /// with the same name, only its changes are written.
     /// * `name` - Name of the collection.
     /// * `collection` - Vector collection to save.
     pub fn save_collection(
@@ -61,8 +88,12 @@ impl Database {
             new = true;
         }
 
-        let value = bincode::serialize(collection)?;
-        self.collections.insert(name, value)?;
+        storage::save(&self.collections, &self.path, name, collection)?;
+
+        // Keep the live handles in sync with the saved collection.
+        if let Some(shared) = self.handles.lock().get(name) {
+            *shared.write() = Some(collection.clone());
+        }
 
         // If it's a new collection, update the count.
         if new {
@@ -75,8 +106,14 @@ impl Database {
     /// Deletes a collection from the database.
     /// * `name` - Collection name to delete.
     pub fn delete_collection(&mut self, name: &str) -> Result<(), Error> {
-        self.collections.remove(name)?;
+        storage::delete(&self.collections, name)?;
         self.count -= 1;
+
+        // Prevent the live handles from saving the collection again.
+        if let Some(shared) = self.handles.lock().remove(name) {
+            *shared.write() = None;
+        }
+
         Ok(())
     }
 
@@ -94,3 +131,342 @@ impl Database {
         self.len()
     }
 }
+
+impl Database {
+    fn from_db(collections: Db, path: &str) -> Self {
+        let count = collections.len();
+        let handles = Mutex::new(HashMap::new());
+        Self { collections, count, path: path.to_string(), handles }
+    }
+}
+
+/// The live handle of a collection stored in the database.
+/// Changes made through the handle are saved immediately. If a
+/// change fails to save, it's discarded and the error returned.
+/// The handle can be cloned and shared across threads.
+#[pyclass(module = "sahomedb.database")]
+#[derive(Clone)]
+pub struct CollectionHandle {
+    name: String,
+    path: String,
+    db: Db,
+    collection: SharedCollection,
+}
+
+// Any modifications to these methods should be reflected in:
+// - py/tests/test_database.py
+// - py/sahomedb/database.pyi
+#[pymethods]
+impl CollectionHandle {
+    /// Returns the name of the collection.
+    #[getter]
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    /// Inserts a vector record into the collection.
+    /// * `record`: Vector record to insert.
+    pub fn insert(&self, record: &Record) -> Result<(), Error> {
+        self.write(|collection| collection.insert(record))
+    }
+
+    #[pyo3(name = "insert_many")]
+    fn py_insert_many(
+        &self,
+        records: Vec<Record>,
+    ) -> Result<Vec<VectorID>, Error> {
+        self.insert_many(&records)
+    }
+
+    /// Deletes a vector record from the collection. Returns true
+    /// if the collection compacted itself after the deletion.
+    /// * `id`: Vector ID to delete.
+    pub fn delete(&self, id: &VectorID) -> Result<bool, Error> {
+        self.write(|collection| collection.delete(id))
+    }
+
+    /// Updates a vector record in the collection.
+    /// * `id`: Vector ID to update.
+    /// * `record`: New vector record.
+    pub fn update(&self, id: &VectorID, record: &Record) -> Result<(), Error> {
+        self.write(|collection| collection.update(id, record))
+    }
+
+    /// Returns the vector record associated with the ID.
+    /// * `id`: Vector ID to retrieve.
+    pub fn get(&self, id: &VectorID) -> Result<Record, Error> {
+        self.read(|collection| collection.get(id))
+    }
+
+    #[pyo3(name = "get_by_key")]
+    fn py_get_by_key(&self, key: RecordKey) -> Result<Record, Error> {
+        self.get_by_key(&key)
+    }
+
+    #[pyo3(name = "delete_by_key")]
+    fn py_delete_by_key(&self, key: RecordKey) -> Result<bool, Error> {
+        self.delete_by_key(&key)
+    }
+
+    /// Inserts the record if its key is new or updates the record
+    /// with the same key. Returns which of them happened.
+    /// * `record`: Vector record with a key.
+    pub fn upsert(&self, record: &Record) -> Result<UpsertResult, Error> {
+        self.write(|collection| collection.upsert(record))
+    }
+
+    #[pyo3(name = "upsert_many")]
+    fn py_upsert_many(
+        &self,
+        records: Vec<Record>,
+    ) -> Result<Vec<UpsertResult>, Error> {
+        self.upsert_many(&records)
+    }
+
+    /// Returns vector records in the collection as a HashMap.
+    pub fn list(&self) -> Result<HashMap<VectorID, Record>, Error> {
+        self.read(|collection| collection.list())
+    }
+
+    #[pyo3(name = "search", signature = (vector, n, params = None))]
+    fn py_search(
+        &self,
+        vector: &Vector,
+        n: usize,
+        params: Option<SearchParams>,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let params = params.unwrap_or_default();
+        self.search_with_params(vector, n, &params)
+    }
+
+    #[pyo3(name = "search_with_filter")]
+    fn py_search_with_filter(
+        &self,
+        vector: &Vector,
+        n: usize,
+        filter: &PyAny,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let filter = Filter::try_from(filter)?;
+        self.search_with_filter(vector, n, &filter)
+    }
+
+    /// Searches the collection for the true nearest neighbors.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    pub fn true_search(
+        &self,
+        vector: &Vector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|collection| collection.true_search(vector, n))
+    }
+
+    /// Searches the collection for the records whose sparse vectors
+    /// have the highest dot product with the query.
+    /// * `sparse`: Sparse vector to search.
+    /// * `n`: Number of records to return.
+    pub fn sparse_search(
+        &self,
+        sparse: &SparseVector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|collection| collection.sparse_search(sparse, n))
+    }
+
+    #[pyo3(name = "hybrid_search")]
+    #[pyo3(signature = (vector, sparse, n, fusion = "rrf", alpha = 0.5, k = 60.0))]
+    fn py_hybrid_search(
+        &self,
+        vector: &Vector,
+        sparse: &SparseVector,
+        n: usize,
+        fusion: &str,
+        alpha: f32,
+        k: f32,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let fusion = Fusion::from(fusion, alpha, k)?;
+        self.hybrid_search(vector, sparse, n, &fusion)
+    }
+
+    /// Rebuilds the index over the remaining records and saves
+    /// the whole collection. Returns the old to new vector IDs.
+    /// * `remap`: Assign consecutive vector IDs to the records.
+    #[pyo3(signature = (remap = false))]
+    pub fn compact(
+        &self,
+        remap: bool,
+    ) -> Result<HashMap<VectorID, VectorID>, Error> {
+        self.write(|collection| collection.compact(remap))
+    }
+
+    /// Returns a detached copy of the collection. Changes to
+    /// the copy are not saved unless it's saved explicitly.
+    pub fn snapshot(&self) -> Result<Collection, Error> {
+        self.read(|collection| Ok(collection.clone()))
+    }
+
+    /// Returns the number of vector records in the collection.
+    pub fn len(&self) -> usize {
+        self.collection.read().as_ref().map_or(0, |c| c.len())
+    }
+
+    /// Returns true if the collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks if the collection contains a vector ID.
+    /// * `id`: Vector ID to check.
+    pub fn contains(&self, id: &VectorID) -> bool {
+        let collection = self.collection.read();
+        collection.as_ref().is_some_and(|c| c.contains(id))
+    }
+
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+}
+
+impl CollectionHandle {
+    /// Inserts multiple vector records into the collection.
+    /// * `records`: List of vector records to insert.
+    pub fn insert_many(
+        &self,
+        records: &[Record],
+    ) -> Result<Vec<VectorID>, Error> {
+        self.write(|collection| collection.insert_many(records))
+    }
+
+    /// Inserts the records with new keys and updates the records
+    /// with existing keys. Returns the result for each record.
+    /// * `records`: Vector records with unique keys.
+    pub fn upsert_many(
+        &self,
+        records: &[Record],
+    ) -> Result<Vec<UpsertResult>, Error> {
+        self.write(|collection| collection.upsert_many(records))
+    }
+
+    /// Returns the vector record associated with the key.
+    /// * `key`: Key of the record to retrieve.
+    pub fn get_by_key(&self, key: &RecordKey) -> Result<Record, Error> {
+        self.read(|collection| collection.get_by_key(key))
+    }
+
+    /// Deletes the vector record associated with the key. Returns
+    /// true if the collection compacted itself after the deletion.
+    /// * `key`: Key of the record to delete.
+    pub fn delete_by_key(&self, key: &RecordKey) -> Result<bool, Error> {
+        self.write(|collection| collection.delete_by_key(key))
+    }
+
+    /// Searches the collection for the nearest neighbors.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    pub fn search(
+        &self,
+        vector: &Vector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|collection| collection.search(vector, n))
+    }
+
+    /// Searches the collection for the nearest neighbors with the
+    /// parameters overriding the configuration for this search.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    /// * `params`: Parameters of the search.
+    pub fn search_with_params(
+        &self,
+        vector: &Vector,
+        n: usize,
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|c| c.search_with_params(vector, n, params))
+    }
+
+    /// Searches the collection for the nearest neighbors
+    /// whose metadata matches the filter.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    /// * `filter`: Metadata filter the results must match.
+    pub fn search_with_filter(
+        &self,
+        vector: &Vector,
+        n: usize,
+        filter: &Filter,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|c| c.search_with_filter(vector, n, filter))
+    }
+
+    /// Searches the collection with both the dense and the sparse
+    /// vector and combines their results with the fusion method.
+    /// * `vector`: Dense vector to search.
+    /// * `sparse`: Sparse vector to search.
+    /// * `n`: Number of records to return.
+    /// * `fusion`: Method to combine the results.
+    pub fn hybrid_search(
+        &self,
+        vector: &Vector,
+        sparse: &SparseVector,
+        n: usize,
+        fusion: &Fusion,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|c| c.hybrid_search(vector, sparse, n, fusion))
+    }
+
+    /// Runs the function with a shared reference to the collection.
+    fn read<T>(
+        &self,
+        f: impl FnOnce(&Collection) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        match self.collection.read().as_ref() {
+            Some(collection) => f(collection),
+            None => Err(Error::collection_not_found()),
+        }
+    }
+
+    /// Runs the function with an exclusive reference to the
+    /// collection and saves the changes to the database.
+    ///
+    /// If the function or the save fails after the collection is
+    /// changed, the collection is loaded again from the database so
+    /// the handles never see the changes of a failed write.
+    pub(crate) fn write<T>(
+        &self,
+        f: impl FnOnce(&mut Collection) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut guard = self.collection.write();
+        let collection = match guard.as_mut() {
+            Some(collection) => collection,
+            None => return Err(Error::collection_not_found()),
+        };
+
+        let result = match f(collection) {
+            Ok(result) => result,
+            Err(err) => {
+                if collection.changes.is_pending() {
+                    *collection = self.load()?;
+                }
+
+                return Err(err);
+            }
+        };
+
+        // The failed save doesn't change the stored collection
+        // because it's written in a single transaction.
+        if let Err(err) =
+            storage::save(&self.db, &self.path, &self.name, collection)
+        {
+            *collection = self.load()?;
+            return Err(err);
+        }
+
+        Ok(result)
+    }
+
+    /// Loads the saved version of the collection.
+    fn load(&self) -> Result<Collection, Error> {
+        storage::load(&self.db, &self.path, &self.name)
+    }
+}
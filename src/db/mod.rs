@@ -1,9 +1,24 @@
 /// The vector database storing collections.
 pub mod database;
 
+// Internal modules.
+mod storage;
+
 use crate::collection::*;
 use crate::func::err::Error;
+use crate::func::filter::Filter;
+use crate::func::utils::*;
+use crate::index::IndexType;
+use crate::key::RecordKey;
+use crate::quantization::{Code, Quantizer};
+use crate::sparse::{Fusion, SparseIndex, SparseVector};
+use crate::vector::{Vector, VectorID, VectorStore};
+use parking_lot::{Mutex, RwLock};
 use pyo3::prelude::*;
-use sled::Db;
+use serde::{Deserialize, Serialize};
+use sled::transaction::Transactional;
+use sled::{Batch, Db, Tree};
+use std::collections::{HashMap, HashSet};
 use std::fs::remove_dir_all;
 use std::path::Path;
+use std::sync::Arc;
@@ -0,0 +1,399 @@
+use super::*;
+
+// The storage layout of a collection in the database:
+// - The default tree maps the collection name to its header.
+// - The vectors, data, keys, sparse, and graph trees store the
+//   vectors, metadata, record keys, sparse vectors, and graph
+//   nodes keyed by the vector ID.
+// This allows saving only the changed records and nodes
+// instead of re-writing the whole collection.
+//
+// The header starts with the storage marker followed by the
+// format version so the collections stored in another format
+// are detected instead of being misread.
+
+/// The marker of the collections stored by this crate.
+const MARKER: &[u8; 4] = b"SAHO";
+
+/// The version of the storage format. It's incremented when the
+/// encoding of the header, the records, or the nodes changes.
+const FORMAT: u32 = 1;
+
+/// The collection attributes other than the per-record values.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    config: Config,
+    relevancy: f32,
+    dimension: usize,
+    count: usize,
+    /// The number of slots including the deleted ones.
+    slots: usize,
+    /// The number of records deleted since the index was built.
+    deleted: usize,
+    /// The number of nodes in each upper layer.
+    layers: Vec<usize>,
+    /// The node to start the searches from.
+    entry: VectorID,
+    indexes: HashMap<String, IndexType>,
+    /// The quantizer of the stored vectors if they're quantized.
+    quantizer: Option<Quantizer>,
+    /// Incremented on every save to detect stale changes.
+    version: u64,
+}
+
+/// The trees storing the per-record values of a collection.
+struct Trees {
+    vectors: Tree,
+    data: Tree,
+    keys: Tree,
+    sparse: Tree,
+    graph: Tree,
+}
+
+impl Trees {
+    /// The suffixes of the tree names after the collection name.
+    const NAMES: [&'static str; 5] =
+        ["vectors", "data", "keys", "sparse", "graph"];
+
+    fn open(db: &Db, name: &str) -> Result<Self, Error> {
+        let vectors = db.open_tree(format!("{name}.vectors"))?;
+        let data = db.open_tree(format!("{name}.data"))?;
+        let keys = db.open_tree(format!("{name}.keys"))?;
+        let sparse = db.open_tree(format!("{name}.sparse"))?;
+        let graph = db.open_tree(format!("{name}.graph"))?;
+        Ok(Self { vectors, data, keys, sparse, graph })
+    }
+}
+
+/// Returns the storage key of a vector ID which sorts
+/// in the same order as the vector IDs.
+fn key(id: &VectorID) -> [u8; 4] {
+    id.0.to_be_bytes()
+}
+
+/// Adds the removal of every stored value of the tree to the batch.
+fn clear(tree: &Tree, batch: &mut Batch) -> Result<(), Error> {
+    for key in tree.iter().keys() {
+        batch.remove(key?);
+    }
+
+    Ok(())
+}
+
+/// Returns the bytes the stored header starts with.
+fn prefix() -> Vec<u8> {
+    [MARKER.as_slice(), &FORMAT.to_be_bytes()].concat()
+}
+
+/// Encodes the header after the storage marker and the format.
+fn encode_header(header: &Header) -> Result<Vec<u8>, Error> {
+    let mut value = prefix();
+    value.extend(bincode::serialize(header)?);
+    Ok(value)
+}
+
+/// Decodes the header if it's stored in the current format.
+fn decode_header(value: &[u8]) -> Result<Header, Error> {
+    match value.strip_prefix(prefix().as_slice()) {
+        Some(value) => Ok(bincode::deserialize(value)?),
+        None => Err(Error::unsupported_format()),
+    }
+}
+
+fn read_header(db: &Db, name: &str) -> Result<Option<Header>, Error> {
+    match db.get(name)? {
+        Some(value) => Ok(Some(decode_header(&value)?)),
+        None => Ok(None),
+    }
+}
+
+/// Saves the collection to the database. Only the changes since
+/// the collection was last loaded or saved with the same name are
+/// written. Otherwise, the whole collection is written.
+/// * `db`: Database storing the collection.
+/// * `path`: Path of the database.
+/// * `name`: Name of the collection.
+/// * `collection`: Collection to save.
+pub fn save(
+    db: &Db,
+    path: &str,
+    name: &str,
+    collection: &Collection,
+) -> Result<(), Error> {
+    let trees = Trees::open(db, name)?;
+    let mut changes = collection.changes.lock();
+
+    // The collection can be saved incrementally only if it's
+    // in sync with the latest version stored under the name.
+    // A collection stored in another format is overwritten.
+    let stored = match db.get(name)? {
+        Some(value) => decode_header(&value).ok().map(|h| h.version),
+        None => None,
+    };
+    let incremental = match (&changes.origin, stored) {
+        (Some(origin), Some(version)) => {
+            origin.path == path
+                && origin.name == name
+                && origin.version == version
+        }
+        _ => false,
+    };
+
+    let mut records = HashSet::new();
+    let mut nodes = HashSet::new();
+    let mut vectors = Batch::default();
+    let mut data = Batch::default();
+    let mut keys = Batch::default();
+    let mut sparse = Batch::default();
+    let mut graph = Batch::default();
+
+    if incremental {
+        records.extend(changes.records.iter().copied());
+        nodes.extend(changes.nodes.iter().copied());
+    } else {
+        // The stored values are removed in the same transaction
+        // as the new ones are written instead of clearing the trees.
+        clear(&trees.vectors, &mut vectors)?;
+        clear(&trees.data, &mut data)?;
+        clear(&trees.keys, &mut keys)?;
+        clear(&trees.sparse, &mut sparse)?;
+        clear(&trees.graph, &mut graph)?;
+        records.extend(collection.vectors.keys().copied());
+        let base_layer = 0..collection.base_layer.len();
+        nodes.extend(base_layer.map(VectorID::from));
+    }
+
+    // Write the records or remove the deleted ones.
+    for id in records.iter() {
+        // The full precision and the quantized vector are stored
+        // depending on which of them the collection keeps, with the
+        // norm of the vector if it's stored normalized.
+        match collection.vectors.contains_key(id) {
+            true => {
+                let vector = collection.vectors.floats.get(id);
+                let code = collection.vectors.codes.get(id);
+                let norm = collection.vectors.norms.get(id);
+                let value = bincode::serialize(&(vector, code, norm))?;
+                vectors.insert(&key(id), value);
+                let metadata = &collection.data[id];
+                data.insert(&key(id), bincode::serialize(metadata)?);
+            }
+            false => {
+                vectors.remove(&key(id));
+                data.remove(&key(id));
+            }
+        }
+
+        match collection.keys.get(id) {
+            Some(record_key) => {
+                keys.insert(&key(id), bincode::serialize(record_key)?);
+            }
+            None => keys.remove(&key(id)),
+        }
+
+        match collection.sparse.get(id) {
+            Some(vector) => {
+                sparse.insert(&key(id), bincode::serialize(vector)?);
+            }
+            None => sparse.remove(&key(id)),
+        }
+    }
+
+    // Write the nodes of the graph in all layers.
+    for id in nodes.iter() {
+        let index = id.0 as usize;
+        let base = match collection.base_layer.get(index) {
+            Some(node) => node,
+            None => continue,
+        };
+
+        let upper: Vec<&UpperNode> = collection
+            .upper_layers
+            .iter()
+            .filter_map(|layer| layer.get(index))
+            .collect();
+
+        graph.insert(&key(id), bincode::serialize(&(base, upper))?);
+    }
+
+    let version = stored.map_or(0, |version| version + 1);
+    let header = Header {
+        config: collection.config.clone(),
+        relevancy: collection.relevancy,
+        dimension: collection.dimension,
+        count: collection.count,
+        slots: collection.slots.len(),
+        deleted: collection.deleted,
+        layers: collection.upper_layers.iter().map(|l| l.len()).collect(),
+        entry: collection.entry,
+        indexes: collection.indexes(),
+        quantizer: collection.vectors.quantizer.clone(),
+        version,
+    };
+
+    // The records, the graph, and the header are written in one
+    // transaction so a failed save leaves the stored version intact.
+    let header = encode_header(&header)?;
+    let trees = (
+        &trees.vectors,
+        &trees.data,
+        &trees.keys,
+        &trees.sparse,
+        &trees.graph,
+        &**db,
+    );
+
+    trees.transaction(
+        |(vectors_tx, data_tx, keys_tx, sparse_tx, graph_tx, db_tx)| {
+            vectors_tx.apply_batch(&vectors)?;
+            data_tx.apply_batch(&data)?;
+            keys_tx.apply_batch(&keys)?;
+            sparse_tx.apply_batch(&sparse)?;
+            graph_tx.apply_batch(&graph)?;
+            db_tx.insert(name, header.as_slice())?;
+            Ok(())
+        },
+    )?;
+
+    // The collection is now in sync with the saved version.
+    let (path, name) = (path.to_string(), name.to_string());
+    changes.origin = Some(Origin { path, name, version });
+    changes.records.clear();
+    changes.nodes.clear();
+
+    Ok(())
+}
+
+/// Loads the collection from the database. The whole collection
+/// is read into memory since the searches need all of it.
+/// * `db`: Database storing the collection.
+/// * `path`: Path of the database.
+/// * `name`: Name of the collection.
+pub fn load(db: &Db, path: &str, name: &str) -> Result<Collection, Error> {
+    let header = match read_header(db, name)? {
+        Some(header) => header,
+        None => return Err(Error::collection_not_found()),
+    };
+
+    let trees = Trees::open(db, name)?;
+
+    // Converts the storage key back to the vector ID.
+    let to_id = |key: &[u8]| -> Result<VectorID, Error> {
+        let bytes: [u8; 4] = match key.try_into() {
+            Ok(bytes) => bytes,
+            Err(_) => return Err("Invalid vector ID in the storage.".into()),
+        };
+
+        Ok(VectorID(u32::from_be_bytes(bytes)))
+    };
+
+    let mut vectors = VectorStore::new(&header.config);
+    vectors.quantizer = header.quantizer;
+    for entry in trees.vectors.iter() {
+        let (key, value) = entry?;
+        let id = to_id(&key)?;
+        let (vector, code, norm): (Option<Vector>, Option<Code>, Option<f32>) =
+            bincode::deserialize(&value)?;
+
+        if let Some(vector) = vector {
+            vectors.floats.insert(id, vector);
+        }
+
+        if let Some(code) = code {
+            vectors.codes.insert(id, code);
+        }
+
+        if let Some(norm) = norm {
+            vectors.norms.insert(id, norm);
+        }
+    }
+
+    let mut data = HashMap::with_capacity(header.count);
+    for entry in trees.data.iter() {
+        let (key, value) = entry?;
+        data.insert(to_id(&key)?, bincode::deserialize(&value)?);
+    }
+
+    let mut keys = HashMap::new();
+    let mut key_ids = HashMap::new();
+    for entry in trees.keys.iter() {
+        let (key, value) = entry?;
+        let id = to_id(&key)?;
+        let record_key: RecordKey = bincode::deserialize(&value)?;
+        key_ids.insert(record_key.clone(), id);
+        keys.insert(id, record_key);
+    }
+
+    // The inverted index is derived from the sparse vectors.
+    let mut sparse = SparseIndex::default();
+    for entry in trees.sparse.iter() {
+        let (key, value) = entry?;
+        let vector: SparseVector = bincode::deserialize(&value)?;
+        sparse.set(&to_id(&key)?, Some(&vector));
+    }
+
+    // Nodes without neighbors are not stored in the graph tree.
+    // The records not in an upper layer have empty nodes in it.
+    let m0 = header.config.m0;
+    let mut base_layer = vec![BaseNode::new(m0); header.slots];
+    let mut upper_layers: Vec<Vec<UpperNode>> = header
+        .layers
+        .iter()
+        .map(|len| vec![UpperNode::default(); *len])
+        .collect();
+
+    for entry in trees.graph.iter() {
+        let (key, value) = entry?;
+        let index = to_id(&key)?.0 as usize;
+        let (base, upper): (BaseNode, Vec<UpperNode>) =
+            bincode::deserialize(&value)?;
+
+        base_layer[index] = base;
+        for (layer, node) in upper_layers.iter_mut().zip(upper) {
+            layer[index] = node;
+        }
+    }
+
+    let slots = (0..header.slots)
+        .map(VectorID::from)
+        .map(|id| if vectors.contains_key(&id) { id } else { INVALID })
+        .collect();
+
+    let mut collection = Collection::new(&header.config);
+    collection.relevancy = header.relevancy;
+    collection.dimension = header.dimension;
+    collection.count = header.count;
+    collection.deleted = header.deleted;
+    collection.vectors = vectors;
+    collection.data = data;
+    collection.keys = keys;
+    collection.key_ids = key_ids;
+    collection.sparse = sparse;
+    collection.slots = slots;
+    collection.base_layer = base_layer;
+    collection.upper_layers = upper_layers;
+    collection.entry = header.entry;
+
+    // The indexes are derived from the data.
+    for (path, kind) in header.indexes {
+        collection.create_index(&path, kind);
+    }
+
+    let (path, name) = (path.to_string(), name.to_string());
+    let origin = Origin { path, name, version: header.version };
+    collection.changes.lock().origin = Some(origin);
+
+    Ok(collection)
+}
+
+/// Deletes the collection and its records from the database.
+/// * `db`: Database storing the collection.
+/// * `name`: Name of the collection.
+pub fn delete(db: &Db, name: &str) -> Result<(), Error> {
+    db.remove(name)?;
+    for tree in Trees::NAMES {
+        db.drop_tree(format!("{name}.{tree}"))?;
+    }
+
+    Ok(())
+}
@@ -13,9 +13,43 @@ pub struct Config {
     /// Layer multiplier. The optimal value is `1/ln(M)`.
     #[pyo3(get, set)]
     pub ml: f32,
+    /// Max number of neighbors of a node in the upper layers.
+    /// Higher values improve the recall at the cost of memory.
+    #[pyo3(get, set)]
+    pub m: usize,
+    /// Max number of neighbors of a node in the base layer.
+    /// It should be at least M. The usual value is `2 * M`.
+    #[pyo3(get, set)]
+    pub m0: usize,
+    /// Consider the neighbors of the nearest nodes when selecting
+    /// the neighbors of a new node. It helps extremely clustered
+    /// data at the cost of a slower construction.
+    #[pyo3(get, set)]
+    pub extend_candidates: bool,
+    /// Fill the free neighbor slots with the candidates pruned by
+    /// the selection heuristic instead of leaving them empty.
+    #[pyo3(get, set)]
+    pub keep_pruned_connections: bool,
     /// Distance calculation function.
     #[pyo3(get)]
     pub distance: Distance,
+    /// Ratio of records deleted since the index was built at
+    /// which the collection compacts itself. None to disable.
+    #[pyo3(get, set)]
+    pub compaction_threshold: Option<f32>,
+    /// Quantization of the stored vectors. None to store them
+    /// in full precision.
+    #[pyo3(get)]
+    pub quantization: Option<Quantization>,
+    /// Re-rank the search results with the full precision vectors
+    /// when they're quantized. The full precision vectors are kept
+    /// in memory for this.
+    #[pyo3(get, set)]
+    pub rerank: bool,
+    /// Number of sub-vectors to split the vectors into
+    /// for product quantization.
+    #[pyo3(get, set)]
+    pub subvectors: usize,
 }
 
 // Any modifications to this methods should be reflected in:
@@ -33,7 +67,20 @@ impl Config {
     ) -> Result<Self, Error> {
         let distance = Distance::from(distance)?;
 
-        Ok(Self { ef_construction, ef_search, ml, distance })
+        Ok(Self {
+            ef_construction,
+            ef_search,
+            ml,
+            m: 32,
+            m0: 64,
+            extend_candidates: false,
+            keep_pruned_connections: false,
+            distance,
+            compaction_threshold: None,
+            quantization: None,
+            rerank: false,
+            subvectors: 8,
+        })
     }
 
     /// Sets the distance calculation function.
@@ -44,6 +91,17 @@ impl Config {
         Ok(())
     }
 
+    /// Sets the quantization of the stored vectors.
+    /// * `quantization`: Quantization, e.g. scalar, or None.
+    #[setter]
+    pub fn set_quantization(
+        &mut self,
+        quantization: Option<&str>,
+    ) -> Result<(), Error> {
+        self.quantization = quantization.map(Quantization::from).transpose()?;
+        Ok(())
+    }
+
     #[staticmethod]
     fn create_default() -> Self {
         Self::default()
@@ -59,13 +117,29 @@ impl Default for Config {
     /// * `ef_construction`: 40
     /// * `ef_search`: 15
     /// * `ml`: 0.3
+    /// * `m`: 32
+    /// * `m0`: 64
+    /// * `extend_candidates`: false
+    /// * `keep_pruned_connections`: false
     /// * `distance`: euclidean
+    /// * `compaction_threshold`: None
+    /// * `quantization`: None
+    /// * `rerank`: false
+    /// * `subvectors`: 8
     fn default() -> Self {
         Self {
             ef_construction: 40,
             ef_search: 15,
             ml: 0.3,
+            m: 32,
+            m0: 64,
+            extend_candidates: false,
+            keep_pruned_connections: false,
             distance: Distance::Euclidean,
+            compaction_threshold: None,
+            quantization: None,
+            rerank: false,
+            subvectors: 8,
         }
     }
 }
@@ -77,24 +151,39 @@ pub struct Collection {
     /// The collection configuration object.
     #[pyo3(get)]
     pub config: Config,
-    /// The min/max distance to consider a neighbor.
+    /// The max distance or the min similarity score of the
+    /// dot and cosine functions to consider a neighbor.
     #[pyo3(get)]
     pub relevancy: f32,
     // Private fields below.
-    data: HashMap<VectorID, Metadata>,
-    vectors: HashMap<VectorID, Vector>,
-    slots: Vec<VectorID>,
-    base_layer: Vec<BaseNode>,
-    upper_layers: Vec<Vec<UpperNode>>,
+    pub(crate) data: HashMap<VectorID, Metadata>,
+    pub(crate) vectors: VectorStore,
+    pub(crate) slots: Vec<VectorID>,
+    pub(crate) base_layer: Vec<BaseNode>,
+    pub(crate) upper_layers: Vec<Vec<UpperNode>>,
+    /// The live node in the highest layer to start the searches
+    /// from. It's invalid if the collection has no records.
+    pub(crate) entry: VectorID,
+    pub(crate) indexes: HashMap<String, FieldIndex>,
+    pub(crate) keys: HashMap<VectorID, RecordKey>,
+    pub(crate) key_ids: HashMap<RecordKey, VectorID>,
+    pub(crate) sparse: SparseIndex,
     // Utility fields.
-    count: usize,
-    dimension: usize,
+    pub(crate) count: usize,
+    pub(crate) dimension: usize,
+    /// Records deleted since the index was built.
+    pub(crate) deleted: usize,
+    #[serde(skip)]
+    pub(crate) changes: ChangeLog,
 }
 
+/// Returns the stored vector which is normalized for the cosine
+/// similarity. Panics if the full precision vector is not stored
+/// because the vectors are quantized. Use `Collection::get` instead.
 impl Index<&VectorID> for Collection {
     type Output = Vector;
     fn index(&self, index: &VectorID) -> &Self::Output {
-        &self.vectors[index]
+        &self.vectors.floats[index]
     }
 }
 
@@ -110,13 +199,20 @@ impl Collection {
         Self {
             count: 0,
             dimension: 0,
+            deleted: 0,
             relevancy: -1.0,
             config: config.clone(),
             data: HashMap::new(),
-            vectors: HashMap::new(),
+            vectors: VectorStore::new(config),
             slots: vec![],
             base_layer: vec![],
             upper_layers: vec![],
+            entry: INVALID,
+            indexes: HashMap::new(),
+            keys: HashMap::new(),
+            key_ids: HashMap::new(),
+            sparse: SparseIndex::default(),
+            changes: ChangeLog::default(),
         }
     }
 
@@ -155,12 +251,18 @@ impl Collection {
             return Err(err);
         }
 
+        validate_keys(&self.key_ids, std::slice::from_ref(record))?;
+
         // Create a new vector ID using the next available slot.
         let id: VectorID = self.slots.len().into();
 
         // Insert the new vector and data.
-        self.vectors.insert(id, record.vector.clone());
+        self.fit_vectors(std::iter::once(&record.vector));
+        self.vectors.insert(id, &record.vector);
         self.data.insert(id, record.data.clone());
+        set_key(&mut self.keys, &mut self.key_ids, &id, record.key.as_ref());
+        self.sparse.set(&id, record.sparse.as_ref());
+        index_record(&mut self.indexes, &id, &self.data[&id]);
 
         // Add new vector id to the slots.
         self.slots.push(id);
@@ -171,6 +273,7 @@ impl Collection {
         // This operation is last because it depends on
         // the updated vectors data.
         self.insert_to_layers(&[id]);
+        self.changes.record(&[id], &[]);
 
         Ok(())
     }
@@ -184,9 +287,12 @@ impl Collection {
         Ok(ids)
     }
 
-    /// Deletes a vector record from the collection.
+    /// Deletes a vector record from the collection. Returns true if
+    /// the deleted records reached the compaction threshold and the
+    /// collection compacted itself. The compaction reclaims the
+    /// deleted slots at the end and the next save is a full save.
     /// * `id`: Vector ID to delete.
-    pub fn delete(&mut self, id: &VectorID) -> Result<(), Error> {
+    pub fn delete(&mut self, id: &VectorID) -> Result<bool, Error> {
         // Ensure the vector ID exists in the collection.
         if !self.contains(id) {
             return Err(Error::record_not_found());
@@ -195,6 +301,9 @@ impl Collection {
         self.delete_from_layers(&[*id]);
 
         // Update the collection data.
+        unindex_record(&mut self.indexes, id, &self.data[id]);
+        set_key(&mut self.keys, &mut self.key_ids, id, None);
+        self.sparse.set(id, None);
         self.vectors.remove(id);
         self.data.remove(id);
 
@@ -203,8 +312,19 @@ impl Collection {
 
         // Update the collection count.
         self.count -= 1;
+        self.deleted += 1;
+        self.changes.record(&[*id], &[]);
+
+        // Rebuild the index once enough records are deleted.
+        if let Some(threshold) = self.config.compaction_threshold {
+            let ratio = self.deleted as f32 / self.slots.len() as f32;
+            if ratio >= threshold {
+                self.compact(false)?;
+                return Ok(true);
+            }
+        }
 
-        Ok(())
+        Ok(false)
     }
 
     /// Returns vector records in the collection as a HashMap.
@@ -215,13 +335,10 @@ impl Collection {
         }
 
         // Map the vectors to a hashmap of records.
-        let mapper = |(id, vector): (&VectorID, &Vector)| {
-            let data = self.data[id].clone();
-            let record = Record::new(vector, &data);
-            (*id, record)
-        };
-
-        let records = self.vectors.par_iter().map(mapper).collect();
+        let view = self.view();
+        let ids: Vec<&VectorID> = self.vectors.keys().collect();
+        let mapper = |id: &&VectorID| (**id, view.record(id));
+        let records = ids.par_iter().map(mapper).collect();
         Ok(records)
     }
 
@@ -232,12 +349,37 @@ impl Collection {
             return Err(Error::record_not_found());
         }
 
-        let vector = self.vectors[id].clone();
-        let data = self.data[id].clone();
-        Ok(Record::new(&vector, &data))
+        Ok(self.view().record(id))
     }
 
-    /// Updates a vector record in the collection.
+    #[pyo3(name = "get_by_key")]
+    fn py_get_by_key(&self, key: RecordKey) -> Result<Record, Error> {
+        self.get_by_key(&key)
+    }
+
+    #[pyo3(name = "delete_by_key")]
+    fn py_delete_by_key(&mut self, key: RecordKey) -> Result<bool, Error> {
+        self.delete_by_key(&key)
+    }
+
+    /// Inserts the record if its key is new or updates the record
+    /// with the same key. Returns which of them happened.
+    /// * `record`: Vector record with a key.
+    pub fn upsert(&mut self, record: &Record) -> Result<UpsertResult, Error> {
+        let results = self.upsert_many(std::slice::from_ref(record))?;
+        Ok(results[0])
+    }
+
+    #[pyo3(name = "upsert_many")]
+    fn py_upsert_many(
+        &mut self,
+        records: Vec<Record>,
+    ) -> Result<Vec<UpsertResult>, Error> {
+        self.upsert_many(&records)
+    }
+
+    /// Updates a vector record in the collection. The sparse
+    /// vector is replaced along with the vector.
     /// * `id`: Vector ID to update.
     /// * `record`: New vector record.
     pub fn update(
@@ -250,107 +392,274 @@ impl Collection {
         }
 
         // Validate the new vector dimension.
-        self.validate_dimension(&record.vector)?;
+        self.view().validate_dimension(&record.vector)?;
+
+        // The key can't be moved from another record.
+        let key_id = record.key.as_ref().and_then(|k| self.key_ids.get(k));
+        if key_id.is_some_and(|key_id| key_id != id) {
+            return Err(Error::key_exists());
+        }
 
         // Remove the old vector from the index layers.
         self.delete_from_layers(&[*id]);
 
         // Insert the updated vector and data.
-        self.vectors.insert(*id, record.vector.clone());
+        unindex_record(&mut self.indexes, id, &self.data[id]);
+        self.fit_vectors(std::iter::once(&record.vector));
+        self.vectors.insert(*id, &record.vector);
         self.data.insert(*id, record.data.clone());
+        self.sparse.set(id, record.sparse.as_ref());
+        index_record(&mut self.indexes, id, &self.data[id]);
         self.insert_to_layers(&[*id]);
 
+        // Records updated without a key keep their key.
+        if record.key.is_some() {
+            set_key(&mut self.keys, &mut self.key_ids, id, record.key.as_ref());
+        }
+
+        self.changes.record(&[*id], &[]);
+
         Ok(())
     }
 
-    /// Searches the collection for the nearest neighbors.
-    /// * `vector`: Vector to search.
-    /// * `n`: Number of neighbors to return.
-    pub fn search(
-        &self,
-        vector: &Vector,
-        n: usize,
-    ) -> Result<Vec<SearchResult>, Error> {
-        let mut search = Search::default();
-
-        // Early return if the collection is empty.
-        if self.vectors.is_empty() {
-            return Ok(vec![]);
-        }
+    /// Rebuilds the index over the remaining records to reclaim
+    /// the memory of the deleted records. Returns the mapping of
+    /// the old vector IDs to the new ones.
+    ///
+    /// Without remapping, the vector IDs stay the same and only
+    /// the deleted slots at the end are reclaimed for new records.
+    /// * `remap`: Assign consecutive vector IDs to the records.
+    #[pyo3(signature = (remap = false))]
+    pub fn compact(
+        &mut self,
+        remap: bool,
+    ) -> Result<HashMap<VectorID, VectorID>, Error> {
+        let mut ids: Vec<VectorID> = self.vectors.keys().copied().collect();
+        ids.sort();
+
+        // The graph is built with the positions of the records as IDs.
+        // The vectors are quantized again only from full precision
+        // because quantizing the decoded vectors loses more precision
+        // on every compaction. Otherwise, the stored codes are kept.
+        let vectors = match self.vectors.keeps_floats() {
+            true => {
+                let iter = ids.iter().map(|id| self.vectors.original(id));
+                let originals: Vec<Vector> = iter.flatten().collect();
+                Self::store_vectors(&self.config, originals.iter())?
+            }
+            false => {
+                let positions: HashMap<VectorID, VectorID> = (ids.iter())
+                    .enumerate()
+                    .map(|(i, id)| (*id, i.into()))
+                    .collect();
+
+                let vectors = std::mem::take(&mut self.vectors);
+                vectors.map_ids(|id| positions[id])
+            }
+        };
 
-        // Ensure the vector dimension matches the collection dimension.
-        self.validate_dimension(vector)?;
+        let (built_base, built_upper) =
+            Self::build_graph(&self.config, &vectors);
+        let new_ids: Vec<VectorID> = match remap {
+            true => (0..ids.len()).map(VectorID::from).collect(),
+            false => ids.clone(),
+        };
 
-        // Find the first valid vector ID from the slots.
-        let slots_iter = self.slots.as_slice().into_par_iter();
-        let vector_id = match slots_iter.find_first(|id| id.is_valid()) {
-            Some(id) => id,
-            None => return Err("Unable to initiate search.".into()),
+        let translate = |id: VectorID| match id.is_valid() {
+            true => new_ids[id.0 as usize],
+            false => INVALID,
         };
 
-        search.visited.resize_capacity(self.vectors.len());
-        search.push(vector_id, vector, &self.vectors);
+        // Returns the number of slots needed for the first nodes.
+        let slots_for = |len: usize| match len {
+            0 => 0,
+            _ => new_ids[len - 1].0 as usize + 1,
+        };
 
-        for layer in LayerID(self.upper_layers.len()).descend() {
-            search.ef = if layer.is_zero() { self.config.ef_search } else { 5 };
+        let m0 = self.config.m0;
+        let mut base_layer = vec![BaseNode::new(m0); slots_for(ids.len())];
+        for (i, node) in built_base.into_iter().enumerate() {
+            let node = node.0.iter().map(|id| translate(*id)).collect();
+            base_layer[new_ids[i].0 as usize] = BaseNode(node);
+        }
 
-            if layer.0 == 0 {
-                let layer = self.base_layer.as_slice();
-                search.search(layer, vector, &self.vectors, M * 2);
-            } else {
-                let layer = self.upper_layers[layer.0 - 1].as_slice();
-                search.search(layer, vector, &self.vectors, M);
+        let mut upper_layers = vec![];
+        for layer in built_upper {
+            let mut nodes = vec![UpperNode::default(); slots_for(layer.len())];
+            for (i, node) in layer.into_iter().enumerate() {
+                let node = node.0.iter().map(|id| translate(*id)).collect();
+                nodes[new_ids[i].0 as usize] = UpperNode(node);
             }
 
-            if !layer.is_zero() {
-                search.cull();
+            upper_layers.push(nodes);
+        }
+
+        let vectors = vectors.map_ids(|id| new_ids[id.0 as usize]);
+
+        let mut data = HashMap::with_capacity(ids.len());
+        let mut keys = HashMap::with_capacity(self.keys.len());
+        let mut sparse = SparseIndex::default();
+        for (old, new) in ids.iter().zip(new_ids.iter()) {
+            data.insert(*new, self.data.remove(old).unwrap());
+            if let Some(key) = self.keys.remove(old) {
+                keys.insert(*new, key);
             }
+
+            sparse.set(new, self.sparse.get(old));
         }
 
-        let map_result = |candidate: Candidate| {
-            let id = candidate.vector_id.0;
-            let distance = candidate.distance.0;
-            let data = self.data[&candidate.vector_id].clone();
-            SearchResult { id, distance, data }
-        };
+        self.key_ids =
+            keys.iter().map(|(id, key)| (key.clone(), *id)).collect();
+        self.keys = keys;
+
+        let slots = (0..base_layer.len())
+            .map(VectorID::from)
+            .map(|id| if vectors.contains_key(&id) { id } else { INVALID })
+            .collect();
+
+        self.vectors = vectors;
+        self.data = data;
+        self.sparse = sparse;
+        self.slots = slots;
+        self.base_layer = base_layer;
+        self.upper_layers = upper_layers;
+        self.entry = new_ids.first().copied().unwrap_or(INVALID);
+        self.deleted = 0;
+
+        // The indexes are rebuilt with the new vector IDs.
+        for (path, kind) in self.indexes() {
+            self.create_index(&path, kind);
+        }
 
-        // Get relevant results and truncate the list.
-        let res = search.iter().map(map_result).collect();
-        let mut relevant = self.truncate_irrelevant_result(res);
-        relevant.truncate(n);
-        Ok(relevant)
+        // The collection can't be saved incrementally anymore
+        // because the stored records are keyed by the old IDs.
+        self.changes = ChangeLog::default();
+
+        Ok(ids.into_iter().zip(new_ids).collect())
     }
 
-    /// Searches the collection for the true nearest neighbors.
-    /// * `vector`: Vector to search.
-    /// * `n`: Number of neighbors to return.
-    pub fn true_search(
+    /// Trains the product quantizer or fits the scalar quantizer on
+    /// the stored vectors and quantizes them. Until then, the vectors
+    /// are stored in full precision. Collections built from records
+    /// are trained automatically, and the scalar quantizer is fitted
+    /// once the inserted vectors reach the fit sample. Does nothing
+    /// for other quantizations.
+    pub fn train(&mut self) -> Result<(), Error> {
+        if self.vectors.train()? {
+            let ids: Vec<VectorID> = self.vectors.keys().copied().collect();
+            self.changes.record(&ids, &[]);
+        }
+
+        Ok(())
+    }
+
+    #[pyo3(name = "search", signature = (vector, n, params = None))]
+    fn py_search(
+        &self,
+        vector: &Vector,
+        n: usize,
+        params: Option<SearchParams>,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let params = params.unwrap_or_default();
+        self.search_with_params(vector, n, &params)
+    }
+
+    #[pyo3(name = "search_with_filter")]
+    fn py_search_with_filter(
+        &self,
+        vector: &Vector,
+        n: usize,
+        filter: &PyAny,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let filter = Filter::try_from(filter)?;
+        self.search_with_filter(vector, n, &filter)
+    }
+
+    #[pyo3(name = "true_search", signature = (vector, n, distance = None))]
+    fn py_true_search(
         &self,
         vector: &Vector,
         n: usize,
+        distance: Option<PyObject>,
     ) -> Result<Vec<SearchResult>, Error> {
-        let mut nearest = Vec::with_capacity(self.vectors.len());
+        let callable = match distance {
+            Some(callable) => callable,
+            None => return self.true_search(vector, n),
+        };
+
+        // Python errors can't be returned from the function
+        // so the first one is kept and raised after the search.
+        let error = Mutex::new(None);
+        let metric = |a: &[f32], b: &[f32]| -> f32 {
+            let result = Python::with_gil(|py| {
+                let value = callable.call1(py, (a.to_vec(), b.to_vec()))?;
+                value.extract::<f32>(py)
+            });
 
-        // Ensure the vector dimension matches the collection dimension.
-        self.validate_dimension(vector)?;
+            result.unwrap_or_else(|err| {
+                error.lock().get_or_insert(err);
+                f32::NAN
+            })
+        };
 
-        // Calculate the distance between the query and each record.
-        // Then, create a search result for each record.
-        for (id, vec) in self.vectors.iter() {
-            let distance = self.config.distance.calculate(vector, vec);
-            let data = self.data[id].clone();
-            let res = SearchResult { id: id.0, distance, data };
-            nearest.push(res);
+        let result = self.true_search_with(vector, n, &metric)?;
+        match error.into_inner() {
+            Some(err) => Err(err.to_string().into()),
+            None => Ok(result),
         }
+    }
 
-        // Sort the nearest neighbors by distance.
-        nearest.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap());
+    /// Searches the collection for the records whose sparse vectors
+    /// have the highest dot product with the query. The distance of
+    /// the results is the dot product and the relevancy is not applied.
+    /// * `sparse`: Sparse vector to search.
+    /// * `n`: Number of records to return.
+    pub fn sparse_search(
+        &self,
+        sparse: &SparseVector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        Ok(self.view().sparse_search(sparse, n))
+    }
 
-        // Remove irrelevant results and truncate the list.
-        let mut res = self.truncate_irrelevant_result(nearest);
-        res.truncate(n);
-        Ok(res)
+    #[pyo3(name = "hybrid_search")]
+    #[pyo3(signature = (vector, sparse, n, fusion = "rrf", alpha = 0.5, k = 60.0))]
+    fn py_hybrid_search(
+        &self,
+        vector: &Vector,
+        sparse: &SparseVector,
+        n: usize,
+        fusion: &str,
+        alpha: f32,
+        k: f32,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let fusion = Fusion::from(fusion, alpha, k)?;
+        self.hybrid_search(vector, sparse, n, &fusion)
+    }
+
+    #[pyo3(name = "create_index")]
+    fn py_create_index(&mut self, path: &str, kind: &str) -> Result<(), Error> {
+        let kind = IndexType::from(kind)?;
+        self.create_index(path, kind);
+        Ok(())
     }
+
+    /// Deletes the index of a metadata field.
+    /// * `path`: Dotted path of the indexed metadata field.
+    pub fn delete_index(&mut self, path: &str) -> Result<(), Error> {
+        match self.indexes.remove(path) {
+            Some(_) => Ok(()),
+            None => Err(Error::index_not_found()),
+        }
+    }
+
+    /// Returns the indexed metadata fields and their types.
+    #[getter]
+    pub fn indexes(&self) -> HashMap<String, IndexType> {
+        let iter = self.indexes.iter();
+        iter.map(|(path, index)| (path.clone(), index.kind())).collect()
+    }
+
     /// Returns the configured vector dimension of the collection.
     #[getter]
     pub fn dimension(&self) -> usize {
@@ -429,15 +738,91 @@ impl Collection {
             return Err(message.into());
         }
 
+        validate_keys(&HashMap::new(), records)?;
+
+        let vectors =
+            Self::store_vectors(config, records.iter().map(|r| &r.vector))?;
+        let (base_layer, upper_layers) = Self::build_graph(config, &vectors);
+
+        let data = records
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (i.into(), item.data.clone()))
+            .collect();
+
+        // Add IDs to the slots.
+        let slots = (0..vectors.len()).map(|i| i.into()).collect();
+
+        let keys: HashMap<VectorID, RecordKey> = records
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| Some((i.into(), item.key.clone()?)))
+            .collect();
+
+        let key_ids = keys.iter().map(|(id, key)| (key.clone(), *id));
+        let key_ids = key_ids.collect();
+
+        let mut sparse = SparseIndex::default();
+        for (i, item) in records.iter().enumerate() {
+            sparse.set(&i.into(), item.sparse.as_ref());
+        }
+
+        Ok(Self {
+            data,
+            vectors,
+            base_layer,
+            upper_layers,
+            entry: VectorID(0),
+            slots,
+            dimension,
+            deleted: 0,
+            indexes: HashMap::new(),
+            keys,
+            key_ids,
+            sparse,
+            changes: ChangeLog::default(),
+            config: config.clone(),
+            count: records.len(),
+            relevancy: -1.0,
+        })
+    }
+
+    /// Stores the vectors with their positions as vector IDs. The
+    /// quantizer is fitted to the vectors and trained on them.
+    /// * `config`: Config of the collection.
+    /// * `vectors`: Vectors to store.
+    fn store_vectors<'a>(
+        config: &Config,
+        vectors: impl Iterator<Item = &'a Vector> + Clone,
+    ) -> Result<VectorStore, Error> {
+        let mut store = VectorStore::new(config);
+        store.fit(vectors.clone());
+        for (i, vector) in vectors.enumerate() {
+            store.insert(i.into(), vector);
+        }
+
+        // The graph is built with the quantized vectors.
+        store.train()?;
+        Ok(store)
+    }
+
+    /// Builds the graph layers over the stored vectors whose vector
+    /// IDs are their positions. The first node is the entry.
+    /// * `config`: Config of the collection.
+    /// * `vectors`: Stored vectors of the nodes.
+    fn build_graph(
+        config: &Config,
+        vectors: &VectorStore,
+    ) -> (Vec<BaseNode>, Vec<Vec<UpperNode>>) {
         // Find the number of layers.
 
-        let mut len = records.len();
+        let mut len = vectors.len();
         let mut layers = Vec::new();
 
         loop {
             let next = (len as f32 * config.ml) as usize;
 
-            if next < M {
+            if next < config.m {
                 break;
             }
 
@@ -454,16 +839,11 @@ impl Collection {
         // Give all vectors a random layer and sort the list of nodes
         // by descending order for construction.
 
-        // This allows us to copy higher layers to lower layers as
+        // This allows us to link the nodes of the higher layers
+        // first so the lower nodes can descend through them as
         // construction progresses, while preserving randomness in
         // each point's layer and insertion order.
 
-        let vectors = records
-            .par_iter()
-            .enumerate()
-            .map(|(i, item)| (i.into(), item.vector.clone()))
-            .collect::<HashMap<VectorID, Vector>>();
-
         // Figure out how many nodes will go on each layer.
         // This helps us allocate memory capacity for each
         // layer in advance, and also helps enable batch
@@ -479,63 +859,53 @@ impl Collection {
 
         // Create index constructor.
 
-        let search_pool = SearchPool::new(vectors.len());
-        let mut upper_layers = vec![vec![]; top_layer.0];
-        let base_layer = vectors
-            .par_iter()
-            .map(|_| RwLock::new(BaseNode::default()))
+        let search_pool =
+            SearchPool::new(vectors.len(), config.distance.clone());
+        let base_layer = (0..vectors.len())
+            .into_par_iter()
+            .map(|_| RwLock::new(BaseNode::new(config.m0)))
             .collect::<Vec<_>>();
 
+        // The first nodes are in the upper layers.
+        let mut upper_layers: Vec<Vec<RwLock<UpperNode>>> = vec![];
+        upper_layers.resize_with(top_layer.0, Vec::new);
+        for (layer, range) in ranges.iter() {
+            if !layer.is_zero() {
+                upper_layers[layer.0 - 1] = (0..range.end)
+                    .map(|_| RwLock::new(UpperNode::new(config.m)))
+                    .collect();
+            }
+        }
+
+        // The first node is the entry in the top layer.
         let state = IndexConstruction {
             base_layer: &base_layer,
+            upper_layers: &upper_layers,
             search_pool,
             top_layer,
-            vectors: &vectors,
+            entry: VectorID(0),
+            vectors,
             config,
+            changes: None,
         };
 
         // Initialize data for layers.
 
         for (layer, range) in ranges {
-            let end = range.end;
-
             range.into_par_iter().for_each(|i: usize| {
-                state.insert(&i.into(), &layer, &upper_layers)
+                state.insert(&i.into(), &layer);
             });
-
-            // Copy the base layer state to the upper layer.
-            if !layer.is_zero() {
-                (&state.base_layer[..end])
-                    .into_par_iter()
-                    .map(|zero| UpperNode::from_zero(&zero.read()))
-                    .collect_into_vec(&mut upper_layers[layer.0 - 1]);
-            }
         }
 
-        let data = records
-            .iter()
-            .enumerate()
-            .map(|(i, item)| (i.into(), item.data.clone()))
-            .collect();
-
-        // Unwrap the base nodes for the base layer.
+        // Unwrap the nodes of the layers.
         let base_iter = base_layer.into_par_iter();
         let base_layer = base_iter.map(|node| node.into_inner()).collect();
+        let upper_layers = upper_layers
+            .into_iter()
+            .map(|layer| layer.into_iter().map(RwLock::into_inner).collect())
+            .collect();
 
-        // Add IDs to the slots.
-        let slots = (0..vectors.len()).map(|i| i.into()).collect();
-
-        Ok(Self {
-            data,
-            vectors,
-            base_layer,
-            upper_layers,
-            slots,
-            dimension,
-            config: config.clone(),
-            count: records.len(),
-            relevancy: -1.0,
-        })
+        (base_layer, upper_layers)
     }
 
     /// Inserts multiple vector records into the collection.
@@ -564,6 +934,8 @@ impl Collection {
             return Err(message.into());
         }
 
+        validate_keys(&self.key_ids, records)?;
+
         // Create new vector IDs for the records.
         let ids: Vec<VectorID> = {
             let first_id = self.slots.len();
@@ -572,9 +944,13 @@ impl Collection {
         };
 
         // Store the new records vector and data.
+        self.fit_vectors(records.iter().map(|record| &record.vector));
         for (id, record) in ids.iter().zip(records.iter()) {
-            self.vectors.insert(*id, record.vector.clone());
+            self.vectors.insert(*id, &record.vector);
             self.data.insert(*id, record.data.clone());
+            set_key(&mut self.keys, &mut self.key_ids, id, record.key.as_ref());
+            self.sparse.set(id, record.sparse.as_ref());
+            index_record(&mut self.indexes, id, &self.data[id]);
         }
 
         // Add new vector IDs to the slots.
@@ -584,113 +960,391 @@ impl Collection {
         self.count += records.len();
 
         self.insert_to_layers(&ids);
+        self.changes.record(&ids, &[]);
         Ok(ids)
     }
 
-    /// Validates a vector dimension against the collection's.
-    fn validate_dimension(&self, vector: &Vector) -> Result<(), Error> {
-        let found = vector.len();
-        let expected = self.dimension;
+    /// Inserts the records with new keys and updates the records
+    /// with existing keys. The graph is updated for all records at
+    /// once. Returns the result for each record in the same order.
+    /// * `records`: Vector records with unique keys.
+    pub fn upsert_many(
+        &mut self,
+        records: &[Record],
+    ) -> Result<Vec<UpsertResult>, Error> {
+        if records.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Validate all records before modifying the collection.
+        let mut keys = HashSet::new();
+        for record in records {
+            let key = match &record.key {
+                Some(key) => key,
+                None => return Err(Error::key_required()),
+            };
+
+            if !keys.insert(key) {
+                return Err(Error::key_exists());
+            }
+        }
+
+        let dimension = match self.vectors.is_empty() && self.dimension == 0 {
+            true => records[0].vector.len(),
+            false => self.dimension,
+        };
+
+        for record in records {
+            let len = record.vector.len();
+            if len != dimension {
+                return Err(Error::invalid_dimension(len, dimension));
+            }
+        }
+
+        // Resolve the vector IDs of the records.
+        let mut next = self.slots.len();
+        let mut results = Vec::with_capacity(records.len());
+        for record in records {
+            let key = record.key.as_ref().unwrap();
+            let result = match self.key_ids.get(key) {
+                Some(id) => UpsertResult { id: *id, inserted: false },
+                None => {
+                    next += 1;
+                    let id = VectorID::from(next - 1);
+                    UpsertResult { id, inserted: true }
+                }
+            };
+
+            results.push(result);
+        }
+
+        if next >= u32::MAX as usize {
+            return Err(Error::collection_limit());
+        }
+
+        self.dimension = dimension;
+
+        // The updated records are re-linked with the new ones.
+        let updated: Vec<VectorID> = results
+            .iter()
+            .filter(|result| !result.inserted)
+            .map(|result| result.id)
+            .collect();
+
+        self.delete_from_layers(&updated);
+        self.fit_vectors(records.iter().map(|record| &record.vector));
+
+        for (result, record) in results.iter().zip(records.iter()) {
+            let id = &result.id;
+            if result.inserted {
+                self.slots.push(*id);
+                self.count += 1;
+            } else {
+                unindex_record(&mut self.indexes, id, &self.data[id]);
+            }
+
+            self.vectors.insert(*id, &record.vector);
+            self.data.insert(*id, record.data.clone());
+            set_key(&mut self.keys, &mut self.key_ids, id, record.key.as_ref());
+            self.sparse.set(id, record.sparse.as_ref());
+            index_record(&mut self.indexes, id, &self.data[id]);
+        }
+
+        let ids: Vec<VectorID> = results.iter().map(|r| r.id).collect();
+        self.insert_to_layers(&ids);
+        self.changes.record(&ids, &[]);
+
+        Ok(results)
+    }
+
+    /// Returns the vector record associated with the key.
+    /// * `key`: Key of the record to retrieve.
+    pub fn get_by_key(&self, key: &RecordKey) -> Result<Record, Error> {
+        self.view().record_by_key(key)
+    }
+
+    /// Deletes the vector record associated with the key. Returns
+    /// true if the collection compacted itself after the deletion.
+    /// * `key`: Key of the record to delete.
+    pub fn delete_by_key(&mut self, key: &RecordKey) -> Result<bool, Error> {
+        match self.key_ids.get(key).copied() {
+            Some(id) => self.delete(&id),
+            None => Err(Error::key_not_found()),
+        }
+    }
+
+    /// Searches the collection for the true nearest neighbors.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    pub fn true_search(
+        &self,
+        vector: &Vector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let view = self.view();
+        let (ids, params) = (self.vectors.keys(), SearchParams::default());
+        view.search_exhaustive(vector, n, ids, &params, &|_| true)
+    }
+
+    /// Searches the collection for the true nearest neighbors with
+    /// a distance function other than the configured one. The vectors
+    /// are compared as they were inserted and the relevancy is not
+    /// applied because it's a value of the configured function.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    /// * `metric`: Distance function to compare the vectors.
+    pub fn true_search_with(
+        &self,
+        vector: &Vector,
+        n: usize,
+        metric: &dyn Metric,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let view = self.view();
+        view.validate_dimension(vector)?;
+
+        // Similarity scores are negated to sort the closest first.
+        let order = |value: f32| match metric.is_similarity() {
+            true => -value,
+            false => value,
+        };
+
+        let mut candidates = vec![];
+        for id in self.vectors.keys() {
+            let other = self.vectors.original(id).unwrap();
+            let value = metric.calculate(&vector.0, &other.0);
+            let distance = order(value).into();
+            candidates.push(Candidate { distance, vector_id: *id });
+        }
+
+        candidates.sort();
+        let results = candidates.iter().take(n).map(|candidate| {
+            let value = order(candidate.distance.0);
+            view.search_result(&candidate.vector_id, value)
+        });
+
+        Ok(results.collect())
+    }
+
+    /// Searches the collection with both the dense and the sparse
+    /// vector and combines their results with the fusion method.
+    /// The distance of the results is the fused score sorted from
+    /// the highest. The relevancy only applies to the dense search.
+    /// * `vector`: Dense vector to search.
+    /// * `sparse`: Sparse vector to search.
+    /// * `n`: Number of records to return.
+    /// * `fusion`: Method to combine the results.
+    pub fn hybrid_search(
+        &self,
+        vector: &Vector,
+        sparse: &SparseVector,
+        n: usize,
+        fusion: &Fusion,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.view().hybrid_search(vector, sparse, n, fusion)
+    }
+
+    /// Searches the collection for the nearest neighbors.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    pub fn search(
+        &self,
+        vector: &Vector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.search_with_params(vector, n, &SearchParams::default())
+    }
+
+    /// Searches the collection for the nearest neighbors with the
+    /// parameters overriding the configuration for this search.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    /// * `params`: Parameters of the search.
+    pub fn search_with_params(
+        &self,
+        vector: &Vector,
+        n: usize,
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.view().search_layers(vector, n, params, &|_| true)
+    }
 
-        if found != expected {
-            Err(Error::invalid_dimension(found, expected))
-        } else {
-            Ok(())
+    /// Searches the collection for the nearest neighbors
+    /// whose metadata matches the filter.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    /// * `filter`: Metadata filter the results must match.
+    pub fn search_with_filter(
+        &self,
+        vector: &Vector,
+        n: usize,
+        filter: &Filter,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.view().search_with_filter(vector, n, filter)
+    }
+
+    /// Creates an index on a metadata field to speed up filtered
+    /// searches. An existing index of the field is replaced.
+    /// * `path`: Dotted path to the metadata field.
+    /// * `kind`: Type of the indexed values.
+    pub fn create_index(&mut self, path: &str, kind: IndexType) {
+        let mut index = FieldIndex::new(kind);
+        for (id, data) in self.data.iter() {
+            index.insert(id, data.get(path));
         }
+
+        self.indexes.insert(path.to_string(), index);
     }
 
-    /// Inserts vector IDs into the index layers.
+    /// Fits the quantizer to the vectors before they're stored.
+    /// The stored vectors quantized by the fit are saved as changed.
+    fn fit_vectors<'a>(&mut self, vectors: impl Iterator<Item = &'a Vector>) {
+        if self.vectors.fit(vectors) {
+            let ids: Vec<VectorID> = self.vectors.keys().copied().collect();
+            self.changes.record(&ids, &[]);
+        }
+    }
+
+    /// Borrows the records and the graph to look up or search them.
+    pub(crate) fn view(&self) -> CollectionView<'_, &[BaseNode], UpperNode> {
+        CollectionView {
+            config: &self.config,
+            relevancy: self.relevancy,
+            data: &self.data,
+            vectors: &self.vectors,
+            slots: &self.slots,
+            indexes: &self.indexes,
+            keys: &self.keys,
+            key_ids: &self.key_ids,
+            sparse: &self.sparse,
+            count: self.count,
+            dimension: self.dimension,
+            base_layer: &self.base_layer,
+            upper_layers: &self.upper_layers,
+            entry: self.entry,
+        }
+    }
+
+    /// Inserts vector IDs into the index layers. Each node gets a
+    /// random level so the layers grow like in the built index.
     fn insert_to_layers(&mut self, ids: &[VectorID]) {
-        // Add new nodes to the base layer.
-        for _ in 0..ids.len() {
-            self.base_layer.push(BaseNode::default());
+        // Add nodes for the new slots to the base layer.
+        let node = BaseNode::new(self.config.m0);
+        self.base_layer.resize(self.slots.len(), node);
+
+        // The level of the entry is found before the new nodes are
+        // in the layers. The entry is never one of them because the
+        // updated records are unlinked before they're inserted.
+        let (entry, top_layer) =
+            (self.entry, level_of(&self.upper_layers, &self.entry));
+
+        // Add the nodes to the upper layers up to their level. The
+        // hierarchy grows by at most one layer per insertion.
+        let max = self.upper_layers.len() + 1;
+        let levels: Vec<LayerID> =
+            ids.iter().map(|_| LayerID::random(self.config.ml, max)).collect();
+
+        for (id, level) in ids.iter().zip(levels.iter()) {
+            set_level(&mut self.upper_layers, id, level, self.config.m);
         }
 
-        let base_layer = self
-            .base_layer
-            .par_iter()
-            .map(|node| RwLock::new(*node))
+        // The nodes are moved into locks for the construction
+        // and moved back after so the graph is not copied.
+        let base_layer = std::mem::take(&mut self.base_layer)
+            .into_iter()
+            .map(RwLock::new)
             .collect::<Vec<_>>();
 
-        let top_layer = match self.upper_layers.is_empty() {
-            true => LayerID(0),
-            false => LayerID(self.upper_layers.len()),
-        };
+        let upper_layers = std::mem::take(&mut self.upper_layers)
+            .into_iter()
+            .map(|layer| layer.into_iter().map(RwLock::new).collect())
+            .collect::<Vec<Vec<_>>>();
 
         // Create a new index construction state.
-        let state = IndexConstruction {
+        let mut state = IndexConstruction {
             base_layer: base_layer.as_slice(),
-            search_pool: SearchPool::new(self.vectors.len()),
+            upper_layers: upper_layers.as_slice(),
+            search_pool: SearchPool::new(
+                base_layer.len(),
+                self.config.distance.clone(),
+            ),
             top_layer,
+            entry,
             vectors: &self.vectors,
             config: &self.config,
+            changes: Some(&self.changes),
         };
 
-        // Insert all vectors into the state.
-        for id in ids {
-            state.insert(id, &top_layer, &self.upper_layers);
-        }
+        // Insert all vectors into the state in parallel.
+        state.promote(ids, &levels);
+        ids.par_iter().zip(levels.par_iter()).for_each(|(id, level)| {
+            state.insert(id, level);
+        });
+
+        // Move the nodes back from the construction state.
+        let entry = state.entry;
+        self.base_layer =
+            base_layer.into_iter().map(RwLock::into_inner).collect();
+        self.upper_layers = upper_layers
+            .into_iter()
+            .map(|layer| layer.into_iter().map(RwLock::into_inner).collect())
+            .collect();
 
-        // Update base layer using the new state.
-        let iter = state.base_layer.into_par_iter();
-        self.base_layer = iter.map(|node| *node.read()).collect();
+        // The new nodes are tracked even without neighbors
+        // so their levels are saved.
+        self.changes.record(&[], ids);
+        self.entry = entry;
     }
 
-    /// Removes vector IDs from all index layers.
+    /// Removes vector IDs from all index layers and reconnects
+    /// the nodes that linked to them.
     fn delete_from_layers(&mut self, ids: &[VectorID]) {
-        // Remove the vectors from the base layer.
-        for id in ids {
-            let base_node = &mut self.base_layer[id.0 as usize];
-            let index = base_node.par_iter().position_first(|x| *x == *id);
-            if let Some(index) = index {
-                base_node.set(index, &INVALID);
-            }
+        let (vectors, config) = (&self.vectors, &self.config);
+        let mut changed =
+            unlink(self.base_layer.as_mut_slice(), ids, vectors, config);
+
+        for layer in self.upper_layers.iter_mut() {
+            changed.extend(unlink(layer.as_mut_slice(), ids, vectors, config));
         }
 
-        // Remove the vector from the upper layers.
-        for layer in LayerID(self.upper_layers.len()).descend() {
-            let upper_layer = match layer.0 > 0 {
-                true => &mut self.upper_layers[layer.0 - 1],
-                false => break,
-            };
+        // The nodes are only in the upper layers while they're live.
+        for id in ids {
+            set_level(&mut self.upper_layers, id, &LayerID(0), self.config.m);
+        }
 
-            for id in ids {
-                let node = &mut upper_layer[id.0 as usize];
-                let index = node.0.par_iter().position_first(|x| *x == *id);
-                if let Some(index) = index {
-                    node.set(index, &INVALID);
-                }
-            }
+        // Replace the entry with a remaining node if it's removed.
+        if ids.contains(&self.entry) {
+            let filter = |id: &VectorID| !ids.contains(id);
+            let entry = find_entry(&self.upper_layers, &self.slots, &filter);
+            self.entry = entry.map_or(INVALID, |(id, _)| id);
         }
+
+        self.changes.record(&[], ids);
+        self.changes.record(&[], &changed);
     }
+}
 
-    /// Truncates the search result based on the relevancy score.
-    fn truncate_irrelevant_result(
-        &self,
-        result: Vec<SearchResult>,
-    ) -> Vec<SearchResult> {
-        // Early return if the relevancy score is not set.
-        if self.relevancy == -1.0 {
-            return result;
-        }
-
-        // For Euclidean distance, relevant results are those
-        // smaller than the relevancy score with best distance of 0.0.
-        if self.config.distance == Distance::Euclidean {
-            return result
-                .into_par_iter()
-                .filter(|r| r.distance <= self.relevancy)
-                .collect();
-        }
-
-        // For other distance metrics, like cosine similarity
-        // and dot product, the relevant results are above
-        // the relevancy score.
-        result
-            .into_par_iter()
-            .filter(|r| r.distance >= self.relevancy)
-            .collect()
+/// Removes the search results that are not relevant enough.
+/// * `result`: Search results to truncate.
+/// * `relevancy`: Relevancy score or -1.0 to keep all.
+/// * `distance`: Distance function of the results.
+pub(crate) fn truncate_irrelevant(
+    result: Vec<SearchResult>,
+    relevancy: f32,
+    distance: &Distance,
+) -> Vec<SearchResult> {
+    // Early return if the relevancy score is not set.
+    if relevancy == -1.0 {
+        return result;
     }
+
+    // Relevant results are at most as far as the relevancy score.
+    // It's the max distance for distance functions like Euclidean
+    // and the min score for similarities like the dot product.
+    let max = distance.to_distance(relevancy);
+    result
+        .into_par_iter()
+        .filter(|r| distance.to_distance(r.distance) <= max)
+        .collect()
 }
 
 /// A record containing a vector and its associated data.
@@ -703,6 +1357,12 @@ pub struct Record {
     /// Data associated with the vector.
     #[pyo3(get)]
     pub data: Metadata,
+    /// Optional key to address the record instead of its ID.
+    #[pyo3(get, set)]
+    pub key: Option<RecordKey>,
+    /// Optional sparse vector like keyword weights for hybrid search.
+    #[pyo3(get, set)]
+    pub sparse: Option<SparseVector>,
 }
 
 // Any modifications to the Python methods should be reflected in:
@@ -711,10 +1371,16 @@ pub struct Record {
 #[pymethods]
 impl Record {
     #[new]
-    fn py_new(vector: Vec<f32>, data: &PyAny) -> Self {
+    #[pyo3(signature = (vector, data, key = None, sparse = None))]
+    fn py_new(
+        vector: Vec<f32>,
+        data: &PyAny,
+        key: Option<RecordKey>,
+        sparse: Option<SparseVector>,
+    ) -> Self {
         let vector = Vector::from(vector);
         let data = Metadata::from(data);
-        Self::new(&vector, &data)
+        Self { key, sparse, ..Self::new(&vector, &data) }
     }
 
     #[setter]
@@ -748,7 +1414,114 @@ impl Record {
 impl Record {
     /// Creates a new record with a vector and data.
     pub fn new(vector: &Vector, data: &Metadata) -> Self {
-        Self { vector: vector.clone(), data: data.clone() }
+        let (vector, data) = (vector.clone(), data.clone());
+        Self { vector, data, key: None, sparse: None }
+    }
+
+    /// Sets the key to address the record with.
+    /// * `key`: Text or integer key of the record.
+    pub fn with_key(mut self, key: impl Into<RecordKey>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Sets the sparse vector to search the record with.
+    /// * `sparse`: Sparse vector like keyword weights.
+    pub fn with_sparse(mut self, sparse: SparseVector) -> Self {
+        self.sparse = Some(sparse);
+        self
+    }
+}
+
+/// The result of upserting a vector record.
+#[pyclass(module = "sahomedb.collection")]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq)]
+pub struct UpsertResult {
+    /// Vector ID of the upserted record.
+    #[pyo3(get)]
+    pub id: VectorID,
+    /// True if the record is inserted, false if it's updated.
+    #[pyo3(get)]
+    pub inserted: bool,
+}
+
+#[pymethods]
+impl UpsertResult {
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// The parameters of a search that override the configuration
+/// of the collection for the search only. This allows searches
+/// with different recall and latency on the same collection.
+#[pyclass(module = "sahomedb.collection")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchParams {
+    /// Nodes to consider in the base layer instead of `ef_search`.
+    #[pyo3(get, set)]
+    pub ef_search: Option<usize>,
+    /// Nodes to consider in the upper layers. Default: 5.
+    #[pyo3(get, set)]
+    pub ef_upper: Option<usize>,
+    /// Relevancy to use instead of the collection relevancy.
+    #[pyo3(get, set)]
+    pub relevancy: Option<f32>,
+    /// Include the vectors of the records in the results.
+    #[pyo3(get, set)]
+    pub include_vectors: bool,
+}
+
+// Any modifications to the Python methods should be reflected in:
+// - py/tests/test_collection.py
+// - py/sahomedb/collection.pyi
+#[pymethods]
+impl SearchParams {
+    #[new]
+    #[pyo3(signature = (
+        ef_search = None,
+        ef_upper = None,
+        relevancy = None,
+        include_vectors = false
+    ))]
+    fn py_new(
+        ef_search: Option<usize>,
+        ef_upper: Option<usize>,
+        relevancy: Option<f32>,
+        include_vectors: bool,
+    ) -> Self {
+        Self { ef_search, ef_upper, relevancy, include_vectors }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl SearchParams {
+    /// Sets the number of nodes to consider in the base layer.
+    pub fn with_ef_search(mut self, ef_search: usize) -> Self {
+        self.ef_search = Some(ef_search);
+        self
+    }
+
+    /// Sets the number of nodes to consider in the upper layers.
+    pub fn with_ef_upper(mut self, ef_upper: usize) -> Self {
+        self.ef_upper = Some(ef_upper);
+        self
+    }
+
+    /// Sets the relevancy of the results.
+    pub fn with_relevancy(mut self, relevancy: f32) -> Self {
+        self.relevancy = Some(relevancy);
+        self
+    }
+
+    /// Includes the vectors of the records in the results.
+    pub fn with_vectors(mut self) -> Self {
+        self.include_vectors = true;
+        self
     }
 }
 
@@ -759,12 +1532,20 @@ pub struct SearchResult {
     /// Vector ID.
     #[pyo3(get)]
     pub id: u32,
-    /// Distance between the query to the collection vector.
+    /// Distance between the query to the collection vector or
+    /// their similarity score for the dot and cosine functions.
+    /// It's the fused score for the hybrid search.
     #[pyo3(get)]
     pub distance: f32,
     /// Data associated with the vector.
     #[pyo3(get)]
     pub data: Metadata,
+    /// Key of the record if it has one.
+    #[pyo3(get)]
+    pub key: Option<RecordKey>,
+    /// Vector of the record if the search includes the vectors.
+    #[pyo3(get)]
+    pub vector: Option<Vector>,
 }
 
 #[pymethods]
@@ -0,0 +1,556 @@
+use super::*;
+
+/// The records of a concurrent collection. They're locked together
+/// so the vectors, data, and indexes are always consistent.
+#[derive(Debug)]
+struct Records {
+    data: HashMap<VectorID, Metadata>,
+    vectors: VectorStore,
+    slots: Vec<VectorID>,
+    indexes: HashMap<String, FieldIndex>,
+    keys: HashMap<VectorID, RecordKey>,
+    key_ids: HashMap<RecordKey, VectorID>,
+    sparse: SparseIndex,
+    count: usize,
+    /// Records deleted since the index was built.
+    deleted: usize,
+    dimension: usize,
+}
+
+/// The records and the graph of a concurrent collection
+/// borrowed while they're locked for reading.
+type ConcurrentView<'a> =
+    CollectionView<'a, &'a [RwLock<BaseNode>], RwLock<UpperNode>>;
+
+/// The collection variant that can be searched and modified from
+/// multiple threads at the same time.
+///
+/// The records are only locked exclusively while they're written.
+/// The graph is then updated with a lock per node so the searches
+/// keep running while the records are linked into the graph or
+/// unlinked from it.
+#[derive(Debug)]
+pub struct ConcurrentCollection {
+    /// The collection configuration object.
+    pub config: Config,
+    /// The min/max distance to consider a neighbor.
+    pub relevancy: f32,
+    // The locks must be acquired in the order of the fields
+    // below to prevent deadlocks.
+    /// Held while a record is unlinked from the graph so the
+    /// updates of the same record never unlink it at once.
+    unlinking: Mutex<()>,
+    records: RwLock<Records>,
+    base_layer: RwLock<Vec<RwLock<BaseNode>>>,
+    upper_layers: RwLock<Vec<Vec<RwLock<UpperNode>>>>,
+    /// The live node in the highest layer to start the searches from.
+    entry: RwLock<VectorID>,
+}
+
+impl ConcurrentCollection {
+    /// Creates an empty collection with the given configuration.
+    pub fn new(config: &Config) -> Self {
+        Self::from(Collection::new(config))
+    }
+
+    /// Inserts a vector record into the collection.
+    /// * `record`: Vector record to insert.
+    pub fn insert(&self, record: &Record) -> Result<VectorID, Error> {
+        let ids = self.insert_many(std::slice::from_ref(record))?;
+        Ok(ids[0])
+    }
+
+    /// Inserts multiple vector records into the collection.
+    /// * `records`: List of vector records to insert.
+    pub fn insert_many(
+        &self,
+        records: &[Record],
+    ) -> Result<Vec<VectorID>, Error> {
+        if records.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let (ids, levels) = {
+            let mut guard = self.records.write();
+            let store = &mut *guard;
+
+            // Make sure the collection is not full after inserting.
+            if store.slots.len() + records.len() >= u32::MAX as usize {
+                return Err(Error::collection_limit());
+            }
+
+            // Sets the collection dimension if it's the first record.
+            if store.vectors.is_empty() && store.dimension == 0 {
+                store.dimension = records[0].vector.len();
+            }
+
+            for record in records {
+                let len = record.vector.len();
+                if len != store.dimension {
+                    let err = Error::invalid_dimension(len, store.dimension);
+                    return Err(err);
+                }
+            }
+
+            validate_keys(&store.key_ids, records)?;
+
+            let first_id = store.slots.len();
+            let ids: Vec<VectorID> = (first_id..first_id + records.len())
+                .map(|i| i.into())
+                .collect();
+
+            store.vectors.fit(records.iter().map(|record| &record.vector));
+            for (id, record) in ids.iter().zip(records.iter()) {
+                store.vectors.insert(*id, &record.vector);
+                store.data.insert(*id, record.data.clone());
+                set_key(
+                    &mut store.keys,
+                    &mut store.key_ids,
+                    id,
+                    record.key.as_ref(),
+                );
+                store.sparse.set(id, record.sparse.as_ref());
+                index_record(&mut store.indexes, id, &store.data[id]);
+            }
+
+            store.slots.extend(ids.iter());
+            store.count += records.len();
+
+            // The nodes are added while the records are locked so
+            // every vector ID in the slots has a node in the graph.
+            let mut base_layer = self.base_layer.write();
+            for _ in ids.iter() {
+                base_layer.push(RwLock::new(BaseNode::new(self.config.m0)));
+            }
+
+            let levels = self.add_to_upper_layers(&ids);
+            (ids, levels)
+        };
+
+        self.insert_to_layers(&ids, &levels);
+        Ok(ids)
+    }
+
+    /// Deletes a vector record from the collection.
+    /// * `id`: Vector ID to delete.
+    pub fn delete(&self, id: &VectorID) -> Result<(), Error> {
+        let _unlinking = self.unlinking.lock();
+
+        {
+            let mut guard = self.records.write();
+            let store = &mut *guard;
+            if !store.vectors.contains_key(id) {
+                return Err(Error::record_not_found());
+            }
+
+            unindex_record(&mut store.indexes, id, &store.data[id]);
+            set_key(&mut store.keys, &mut store.key_ids, id, None);
+            store.sparse.set(id, None);
+            store.vectors.remove(id);
+            store.data.remove(id);
+
+            // Make the slot invalid so it won't be used again.
+            store.slots[id.0 as usize] = INVALID;
+            store.count -= 1;
+            store.deleted += 1;
+            self.replace_entry(store, id);
+        }
+
+        self.delete_from_layers(id);
+        Ok(())
+    }
+
+    /// Updates a vector record in the collection. The sparse
+    /// vector is replaced along with the vector.
+    /// * `id`: Vector ID to update.
+    /// * `record`: New vector record.
+    pub fn update(&self, id: &VectorID, record: &Record) -> Result<(), Error> {
+        let _unlinking = self.unlinking.lock();
+
+        {
+            let mut guard = self.records.write();
+            let store = &mut *guard;
+            if !store.vectors.contains_key(id) {
+                return Err(Error::record_not_found());
+            }
+
+            let len = record.vector.len();
+            if len != store.dimension {
+                let err = Error::invalid_dimension(len, store.dimension);
+                return Err(err);
+            }
+
+            // The key can't be moved from another record.
+            let key = record.key.as_ref();
+            let key_id = key.and_then(|k| store.key_ids.get(k));
+            if key_id.is_some_and(|key_id| key_id != id) {
+                return Err(Error::key_exists());
+            }
+
+            unindex_record(&mut store.indexes, id, &store.data[id]);
+            store.vectors.fit(std::iter::once(&record.vector));
+            store.vectors.insert(*id, &record.vector);
+            store.data.insert(*id, record.data.clone());
+            store.sparse.set(id, record.sparse.as_ref());
+            index_record(&mut store.indexes, id, &store.data[id]);
+
+            // Records updated without a key keep their key.
+            if key.is_some() {
+                set_key(&mut store.keys, &mut store.key_ids, id, key);
+            }
+
+            self.replace_entry(store, id);
+        }
+
+        // The node is unlinked from its old position
+        // before it's linked at the new one.
+        self.delete_from_layers(id);
+        let levels = self.add_to_upper_layers(&[*id]);
+        self.insert_to_layers(&[*id], &levels);
+        Ok(())
+    }
+
+    /// Returns the vector record associated with the ID.
+    /// * `id`: Vector ID to retrieve.
+    pub fn get(&self, id: &VectorID) -> Result<Record, Error> {
+        self.read(|view| match view.vectors.contains_key(id) {
+            true => Ok(view.record(id)),
+            false => Err(Error::record_not_found()),
+        })
+    }
+
+    /// Returns the vector record associated with the key.
+    /// * `key`: Key of the record to retrieve.
+    pub fn get_by_key(&self, key: &RecordKey) -> Result<Record, Error> {
+        self.read(|view| view.record_by_key(key))
+    }
+
+    /// Searches the collection for the nearest neighbors.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    pub fn search(
+        &self,
+        vector: &Vector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.search_with_params(vector, n, &SearchParams::default())
+    }
+
+    /// Searches the collection for the nearest neighbors with the
+    /// parameters overriding the configuration for this search.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    /// * `params`: Parameters of the search.
+    pub fn search_with_params(
+        &self,
+        vector: &Vector,
+        n: usize,
+        params: &SearchParams,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|view| view.search_layers(vector, n, params, &|_| true))
+    }
+
+    /// Searches the collection for the nearest neighbors
+    /// whose metadata matches the filter.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    /// * `filter`: Metadata filter the results must match.
+    pub fn search_with_filter(
+        &self,
+        vector: &Vector,
+        n: usize,
+        filter: &Filter,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|view| view.search_with_filter(vector, n, filter))
+    }
+
+    /// Searches the collection for the true nearest neighbors.
+    /// * `vector`: Vector to search.
+    /// * `n`: Number of neighbors to return.
+    pub fn true_search(
+        &self,
+        vector: &Vector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|view| {
+            let (ids, params) = (view.vectors.keys(), SearchParams::default());
+            view.search_exhaustive(vector, n, ids, &params, &|_| true)
+        })
+    }
+
+    /// Searches the collection for the records whose sparse vectors
+    /// have the highest dot product with the query.
+    /// * `sparse`: Sparse vector to search.
+    /// * `n`: Number of records to return.
+    pub fn sparse_search(
+        &self,
+        sparse: &SparseVector,
+        n: usize,
+    ) -> Result<Vec<SearchResult>, Error> {
+        Ok(self.read(|view| view.sparse_search(sparse, n)))
+    }
+
+    /// Searches the collection with both the dense and the sparse
+    /// vector and combines their results with the fusion method.
+    /// * `vector`: Dense vector to search.
+    /// * `sparse`: Sparse vector to search.
+    /// * `n`: Number of records to return.
+    /// * `fusion`: Method to combine the results.
+    pub fn hybrid_search(
+        &self,
+        vector: &Vector,
+        sparse: &SparseVector,
+        n: usize,
+        fusion: &Fusion,
+    ) -> Result<Vec<SearchResult>, Error> {
+        self.read(|view| view.hybrid_search(vector, sparse, n, fusion))
+    }
+
+    /// Creates an index on a metadata field to speed up filtered
+    /// searches. An existing index of the field is replaced.
+    /// * `path`: Dotted path to the metadata field.
+    /// * `kind`: Type of the indexed values.
+    pub fn create_index(&self, path: &str, kind: IndexType) {
+        let mut store = self.records.write();
+        let mut index = FieldIndex::new(kind);
+        for (id, data) in store.data.iter() {
+            index.insert(id, data.get(path));
+        }
+
+        store.indexes.insert(path.to_string(), index);
+    }
+
+    /// Returns the number of vector records in the collection.
+    pub fn len(&self) -> usize {
+        self.records.read().count
+    }
+
+    /// Returns true if the collection is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks if the collection contains a vector ID.
+    /// * `id`: Vector ID to check.
+    pub fn contains(&self, id: &VectorID) -> bool {
+        self.records.read().vectors.contains_key(id)
+    }
+
+    /// Returns a copy of the collection at this point in time.
+    /// The copy is saved as a whole when saved to a database.
+    pub fn snapshot(&self) -> Collection {
+        let store = self.records.read();
+        let base_layer = self.base_layer.read();
+        let upper_layers = self.upper_layers.read();
+
+        let mut collection = Collection::new(&self.config);
+        collection.relevancy = self.relevancy;
+        collection.data = store.data.clone();
+        collection.vectors = store.vectors.clone();
+        collection.slots = store.slots.clone();
+        collection.indexes = store.indexes.clone();
+        collection.keys = store.keys.clone();
+        collection.key_ids = store.key_ids.clone();
+        collection.sparse = store.sparse.clone();
+        collection.count = store.count;
+        collection.deleted = store.deleted;
+        collection.dimension = store.dimension;
+        collection.base_layer =
+            base_layer.iter().map(|n| n.read().clone()).collect();
+        collection.upper_layers = upper_layers
+            .iter()
+            .map(|layer| layer.iter().map(|n| n.read().clone()).collect())
+            .collect();
+
+        collection.entry = *self.entry.read();
+
+        collection
+    }
+
+    /// Locks the records and the graph for reading to look up
+    /// or search them.
+    fn read<T>(&self, f: impl FnOnce(&ConcurrentView) -> T) -> T {
+        let store = self.records.read();
+        let base_layer = self.base_layer.read();
+        let upper_layers = self.upper_layers.read();
+        let entry = *self.entry.read();
+
+        f(&CollectionView {
+            config: &self.config,
+            relevancy: self.relevancy,
+            data: &store.data,
+            vectors: &store.vectors,
+            slots: &store.slots,
+            indexes: &store.indexes,
+            keys: &store.keys,
+            key_ids: &store.key_ids,
+            sparse: &store.sparse,
+            count: store.count,
+            dimension: store.dimension,
+            base_layer: base_layer.as_slice(),
+            upper_layers: upper_layers.as_slice(),
+            entry,
+        })
+    }
+
+    /// Adds the nodes to the upper layers up to their random level
+    /// and returns the levels. The new records must be locked until
+    /// their nodes are added so they're added before they're linked.
+    fn add_to_upper_layers(&self, ids: &[VectorID]) -> Vec<LayerID> {
+        let mut upper_layers = self.upper_layers.write();
+        let (ml, max) = (self.config.ml, upper_layers.len() + 1);
+        let levels: Vec<LayerID> =
+            ids.iter().map(|_| LayerID::random(ml, max)).collect();
+
+        for (id, level) in ids.iter().zip(levels.iter()) {
+            set_level(&mut upper_layers, id, level, self.config.m);
+        }
+
+        levels
+    }
+
+    /// Creates the index construction state to link the nodes.
+    fn construction<'a>(
+        &'a self,
+        store: &'a Records,
+        base_layer: &'a [RwLock<BaseNode>],
+        upper_layers: &'a [Vec<RwLock<UpperNode>>],
+        entry: VectorID,
+        search_pool: SearchPool,
+    ) -> IndexConstruction<'a> {
+        IndexConstruction {
+            base_layer,
+            upper_layers,
+            search_pool,
+            top_layer: level_of(upper_layers, &entry),
+            entry,
+            vectors: &store.vectors,
+            config: &self.config,
+            changes: None,
+        }
+    }
+
+    /// Links the inserted vector IDs into the graph. Only the
+    /// nodes whose neighbors change are locked exclusively.
+    /// * `ids`: Vector IDs of the new nodes.
+    /// * `levels`: Highest layer of each node.
+    fn insert_to_layers(&self, ids: &[VectorID], levels: &[LayerID]) {
+        let (promoted, mut search_pool) = {
+            let store = self.records.read();
+            let base_layer = self.base_layer.read();
+            let upper_layers = self.upper_layers.read();
+            let distance = self.config.distance.clone();
+            let search_pool = SearchPool::new(base_layer.len(), distance);
+
+            // The entry is locked while a node above it is linked so
+            // the insertions never promote their nodes at the same time.
+            let mut entry = self.entry.write();
+            let mut state = self.construction(
+                &store,
+                &base_layer,
+                &upper_layers,
+                *entry,
+                search_pool,
+            );
+
+            state.promote(ids, levels);
+            *entry = state.entry;
+            (state.entry, state.search_pool)
+        };
+
+        // The locks are acquired for each node so a waiting writer,
+        // and the searches queued behind it, only wait for one node
+        // to be linked instead of all of them.
+        for (id, level) in ids.iter().zip(levels.iter()) {
+            let store = self.records.read();
+
+            // The record might be deleted by another thread.
+            if *id == promoted || !store.vectors.contains_key(id) {
+                continue;
+            }
+
+            let base_layer = self.base_layer.read();
+            let upper_layers = self.upper_layers.read();
+            let entry = *self.entry.read();
+            let state = self.construction(
+                &store,
+                &base_layer,
+                &upper_layers,
+                entry,
+                search_pool,
+            );
+
+            state.insert(id, level);
+            search_pool = state.search_pool;
+        }
+    }
+
+    /// Replaces the entry with another live node if it's the vector
+    /// ID. This must be called while the records are locked for
+    /// writing so the searches never start from a removed node.
+    fn replace_entry(&self, store: &Records, id: &VectorID) {
+        let upper_layers = self.upper_layers.read();
+        let mut entry = self.entry.write();
+        if *entry == *id {
+            let filter = |other: &VectorID| other != id;
+            let found = find_entry(&upper_layers, &store.slots, &filter);
+            *entry = found.map_or(INVALID, |(id, _)| id);
+        }
+    }
+
+    /// Removes the vector ID from all index layers and reconnects
+    /// the nodes that linked to it. Only the changed nodes are locked
+    /// exclusively so the searches keep running.
+    fn delete_from_layers(&self, id: &VectorID) {
+        let store = self.records.read();
+        let base_layer = self.base_layer.read();
+        let upper_layers = self.upper_layers.read();
+
+        let ids = [*id];
+        let (vectors, config) = (&store.vectors, &self.config);
+        unlink(&mut base_layer.as_slice(), &ids, vectors, config);
+        for layer in upper_layers.iter() {
+            unlink(&mut layer.as_slice(), &ids, vectors, config);
+
+            // The node is only in the upper layers while it's live.
+            if let Some(node) = layer.get(id.0 as usize) {
+                *node.write() = UpperNode::default();
+            }
+        }
+    }
+}
+
+impl From<Collection> for ConcurrentCollection {
+    fn from(collection: Collection) -> Self {
+        let records = Records {
+            data: collection.data,
+            vectors: collection.vectors,
+            slots: collection.slots,
+            indexes: collection.indexes,
+            keys: collection.keys,
+            key_ids: collection.key_ids,
+            sparse: collection.sparse,
+            count: collection.count,
+            deleted: collection.deleted,
+            dimension: collection.dimension,
+        };
+
+        let iter = collection.base_layer.into_iter();
+        let base_layer = iter.map(RwLock::new).collect();
+        let upper_layers = collection
+            .upper_layers
+            .into_iter()
+            .map(|layer| layer.into_iter().map(RwLock::new).collect())
+            .collect();
+
+        Self {
+            config: collection.config,
+            relevancy: collection.relevancy,
+            unlinking: Mutex::new(()),
+            records: RwLock::new(records),
+            base_layer: RwLock::new(base_layer),
+            upper_layers: RwLock::new(upper_layers),
+            entry: RwLock::new(collection.entry),
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use super::*;
+use serde::{Deserializer, Serializer};
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock};
 
 /// The distance function used for similarity calculations.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[derive(PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Distance {
     /// Dot product function.
@@ -10,6 +14,22 @@ pub enum Distance {
     Euclidean,
     /// Cosine similarity function.
     Cosine,
+    /// Number of dimensions with different signs. It's the Hamming
+    /// distance of the vectors converted to sign bits.
+    Hamming,
+    /// Manhattan (L1) distance function.
+    Manhattan,
+    /// Chebyshev (L-infinity) distance function.
+    Chebyshev,
+    /// Squared Euclidean distance function. It ranks the vectors
+    /// like the Euclidean distance without the square root.
+    SquaredEuclidean,
+    /// Jaccard distance function of vectors with non-negative
+    /// values like sparse or binary vectors. It's the weighted
+    /// Jaccard distance which is the set distance for 0/1 values.
+    Jaccard,
+    /// Distance function registered with `Distance::register`.
+    Custom(CustomMetric),
 }
 
 impl Distance {
@@ -18,41 +38,151 @@ impl Distance {
     /// * `dot`: Dot product function.
     /// * `euclidean`: Euclidean distance function.
     /// * `cosine`: Cosine similarity function.
+    /// * `hamming`: Hamming distance of the sign bits.
+    /// * `manhattan`: Manhattan (L1) distance function.
+    /// * `chebyshev`: Chebyshev (L-infinity) distance function.
+    /// * `squared_euclidean`: Squared Euclidean distance function.
+    /// * `jaccard`: Weighted Jaccard distance function.
+    ///
+    /// Other names are looked up in the registered functions.
     pub fn from(distance: &str) -> Result<Self, Error> {
         match distance {
             "dot" => Ok(Distance::Dot),
             "euclidean" => Ok(Distance::Euclidean),
             "cosine" => Ok(Distance::Cosine),
-            _ => Err("Distance function not supported.".into()),
+            "hamming" => Ok(Distance::Hamming),
+            "manhattan" => Ok(Distance::Manhattan),
+            "chebyshev" => Ok(Distance::Chebyshev),
+            "squared_euclidean" => Ok(Distance::SquaredEuclidean),
+            "jaccard" => Ok(Distance::Jaccard),
+            _ => match CustomMetric::get(distance) {
+                Some(metric) => Ok(Distance::Custom(metric)),
+                None => Err("Distance function not supported.".into()),
+            },
         }
     }
 
-    /// Calculates the distance between two vectors.
+    /// Registers a custom distance function by name so it can be
+    /// used in the collection config like the built-in functions.
+    ///
+    /// The function itself is not saved with the collection. It must
+    /// be registered again by the same name before the collection is
+    /// loaded with `Database::get_collection`.
+    /// * `name`: Name of the function. Built-in names are reserved.
+    /// * `metric`: Distance function implementation.
+    pub fn register(
+        name: &str,
+        metric: impl Metric + 'static,
+    ) -> Result<Self, Error> {
+        if let Ok(Distance::Custom(_)) | Err(_) = Distance::from(name) {
+            let metric = CustomMetric::register(name, Box::new(metric));
+            return Ok(Distance::Custom(metric));
+        }
+
+        Err("The distance function name is reserved.".into())
+    }
+
+    /// Returns true if higher values of the function mean more
+    /// similar vectors like the dot product and cosine similarity.
+    pub fn is_similarity(&self) -> bool {
+        match self {
+            Distance::Dot | Distance::Cosine => true,
+            Distance::Custom(metric) => metric.0.metric.is_similarity(),
+            _ => false,
+        }
+    }
+
+    /// Converts the value of the function to a distance where lower
+    /// is closer. Similarity scores are negated so the values of all
+    /// functions are sorted the same way.
+    /// * `value`: Value calculated by the function.
+    pub fn to_distance(&self, value: f32) -> f32 {
+        match self.is_similarity() {
+            true => -value,
+            false => value,
+        }
+    }
+
+    /// Converts the distance back to the value of the function.
+    /// * `distance`: Distance returned by `to_distance`.
+    pub fn from_distance(&self, distance: f32) -> f32 {
+        self.to_distance(distance)
+    }
+
+    /// Calculates the distance between two vectors with the
+    /// fastest SIMD kernel supported by the CPU. The dimensions
+    /// are validated where the vectors enter the collection.
     pub fn calculate(&self, a: &Vector, b: &Vector) -> f32 {
-        assert_eq!(a.0.len(), b.0.len());
+        debug_assert_eq!(a.0.len(), b.0.len());
+        let kernel = Kernel::detect();
         match self {
-            Distance::Dot => Distance::dot(a, b),
-            Distance::Euclidean => Distance::euclidean(a, b),
-            Distance::Cosine => Distance::cosine(a, b),
+            Distance::Dot => kernel.dot(&a.0, &b.0),
+            Distance::Euclidean => kernel.squared(&a.0, &b.0).sqrt(),
+            Distance::SquaredEuclidean => kernel.squared(&a.0, &b.0),
+            Distance::Cosine => kernel.cosine(&a.0, &b.0),
+            Distance::Custom(metric) => metric.0.metric.calculate(&a.0, &b.0),
+            _ => self.calculate_iter(a, b.0.iter().copied()),
         }
     }
 
-    // List additional distance functions below.
-    fn dot(a: &Vector, b: &Vector) -> f32 {
-        let zip = a.0.iter().zip(b.0.iter());
-        zip.map(|(x, y)| x * y).sum()
+    /// Calculates the distance between the vector and the values,
+    /// e.g. the decoded values of a quantized vector.
+    pub fn calculate_iter(
+        &self,
+        a: &Vector,
+        b: impl Iterator<Item = f32>,
+    ) -> f32 {
+        if let Distance::Custom(metric) = self {
+            let b: Vec<f32> = b.collect();
+            return metric.0.metric.calculate(&a.0, &b);
+        }
+
+        let zip = a.0.iter().copied().zip(b);
+        match self {
+            Distance::Dot => zip.map(|(x, y)| x * y).sum(),
+            Distance::Euclidean => {
+                zip.map(|(x, y)| (x - y).powi(2)).sum::<f32>().sqrt()
+            }
+            Distance::SquaredEuclidean => {
+                zip.map(|(x, y)| (x - y).powi(2)).sum()
+            }
+            Distance::Cosine => {
+                let (mut dot, mut ma, mut mb) = (0.0, 0.0, 0.0);
+                for (x, y) in zip {
+                    dot += x * y;
+                    ma += x * x;
+                    mb += y * y;
+                }
+
+                dot / (ma.sqrt() * mb.sqrt())
+            }
+            Distance::Hamming => Distance::hamming(zip),
+            Distance::Manhattan => zip.map(|(x, y)| (x - y).abs()).sum(),
+            Distance::Chebyshev => {
+                zip.map(|(x, y)| (x - y).abs()).fold(0.0, f32::max)
+            }
+            Distance::Jaccard => Distance::jaccard(zip),
+            Distance::Custom(_) => unreachable!(),
+        }
     }
 
-    fn cosine(a: &Vector, b: &Vector) -> f32 {
-        let dot = Self::dot(a, b);
-        let ma = a.0.iter().map(|x| x.powi(2)).sum::<f32>().sqrt();
-        let mb = b.0.iter().map(|y| y.powi(2)).sum::<f32>().sqrt();
-        dot / (ma * mb)
+    // List additional distance functions below.
+    fn hamming(zip: impl Iterator<Item = (f32, f32)>) -> f32 {
+        zip.filter(|(x, y)| (*x > 0.0) != (*y > 0.0)).count() as f32
     }
 
-    fn euclidean(a: &Vector, b: &Vector) -> f32 {
-        let zip = a.0.iter().zip(b.0.iter());
-        zip.map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt()
+    fn jaccard(zip: impl Iterator<Item = (f32, f32)>) -> f32 {
+        let (mut min, mut max) = (0.0, 0.0);
+        for (x, y) in zip {
+            min += x.min(y);
+            max += x.max(y);
+        }
+
+        // Empty vectors are identical.
+        match max > 0.0 {
+            true => 1.0 - min / max,
+            false => 0.0,
+        }
     }
 }
 
@@ -69,6 +199,130 @@ impl IntoPy<Py<PyAny>> for Distance {
             Distance::Dot => "dot".into_py(py),
             Distance::Euclidean => "euclidean".into_py(py),
             Distance::Cosine => "cosine".into_py(py),
+            Distance::Hamming => "hamming".into_py(py),
+            Distance::Manhattan => "manhattan".into_py(py),
+            Distance::Chebyshev => "chebyshev".into_py(py),
+            Distance::SquaredEuclidean => "squared_euclidean".into_py(py),
+            Distance::Jaccard => "jaccard".into_py(py),
+            Distance::Custom(metric) => metric.name().into_py(py),
+        }
+    }
+}
+
+/// The distance function implemented outside of the crate. It's
+/// implemented for functions and closures returning the distance.
+pub trait Metric: Send + Sync {
+    /// Calculates the distance between the values of two vectors.
+    fn calculate(&self, a: &[f32], b: &[f32]) -> f32;
+
+    /// Returns true if higher values mean more similar vectors.
+    fn is_similarity(&self) -> bool {
+        false
+    }
+}
+
+impl<F: Fn(&[f32], &[f32]) -> f32 + Send + Sync> Metric for F {
+    fn calculate(&self, a: &[f32], b: &[f32]) -> f32 {
+        self(a, b)
+    }
+}
+
+/// The registered distance function and its name.
+struct Registered {
+    name: String,
+    metric: Box<dyn Metric>,
+}
+
+/// The handle of a registered distance function. It's saved by
+/// name and resolved from the registered functions when loaded.
+#[derive(Clone)]
+pub struct CustomMetric(Arc<Registered>);
+
+impl CustomMetric {
+    /// Returns the functions registered by name.
+    fn registry() -> &'static RwLock<HashMap<String, CustomMetric>> {
+        static REGISTRY: OnceLock<RwLock<HashMap<String, CustomMetric>>> =
+            OnceLock::new();
+        REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+    }
+
+    /// Registers the function by name replacing the previous one.
+    /// The previous function is kept until the collections using
+    /// it are dropped.
+    fn register(name: &str, metric: Box<dyn Metric>) -> Self {
+        let registered = Registered { name: name.to_string(), metric };
+        let metric = CustomMetric(Arc::new(registered));
+        Self::registry().write().insert(name.to_string(), metric.clone());
+        metric
+    }
+
+    /// Returns the function registered by the name if any.
+    fn get(name: &str) -> Option<Self> {
+        Self::registry().read().get(name).cloned()
+    }
+
+    /// Returns the name of the function.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+}
+
+// The functions are compared by name because that's how they're
+// saved and resolved when the collection is loaded.
+
+impl PartialEq for CustomMetric {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for CustomMetric {}
+
+impl PartialOrd for CustomMetric {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CustomMetric {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name().cmp(other.name())
+    }
+}
+
+impl Hash for CustomMetric {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name().hash(state);
+    }
+}
+
+impl Debug for CustomMetric {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_tuple("CustomMetric").field(&self.name()).finish()
+    }
+}
+
+impl Serialize for CustomMetric {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for CustomMetric {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match CustomMetric::get(&name) {
+            Some(metric) => Ok(metric),
+            None => {
+                let message =
+                    format!("Distance function {name} is not registered.");
+                Err(serde::de::Error::custom(message))
+            }
         }
     }
 }
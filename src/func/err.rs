@@ -3,6 +3,7 @@ use super::*;
 // Other error types.
 use bincode::ErrorKind as BincodeError;
 use pyo3::exceptions::PyValueError;
+use sled::transaction::TransactionError;
 use sled::Error as SledError;
 use std::error::Error as StandardError;
 use std::io::Error as IOError;
@@ -39,14 +40,54 @@ impl Error {
         message.into()
     }
 
+    /// Creates error when the collection is stored in a format
+    /// this version can't read.
+    pub fn unsupported_format() -> Self {
+        let brief = "The storage format of the collection is unsupported.";
+        let detail = "It's saved by a different version.";
+        let message = format!("{brief} {detail}");
+        message.into()
+    }
+
+    /// Creates error when the metadata field index is not found.
+    pub fn index_not_found() -> Self {
+        let message = "The metadata field index is not found.";
+        message.into()
+    }
+
     // Common record errors.
 
+    /// Creates error when the record key is not found.
+    pub fn key_not_found() -> Self {
+        let message = "The record key is not found.";
+        message.into()
+    }
+
+    /// Creates error when the record key is missing.
+    pub fn key_required() -> Self {
+        let message = "The record key is required.";
+        message.into()
+    }
+
+    /// Creates error when the record key is already used.
+    pub fn key_exists() -> Self {
+        let message = "The record key already exists.";
+        message.into()
+    }
+
     /// Creates error when vector record is not found.
     pub fn record_not_found() -> Self {
         let message = "The vector record is not found.";
         message.into()
     }
 
+    /// Creates error when a filter can't be parsed or converted.
+    /// * `detail`: Reason the filter is invalid.
+    pub fn invalid_filter(detail: &str) -> Self {
+        let message = format!("Invalid filter. {detail}.");
+        message.into()
+    }
+
     /// Creates error when getting vector with invalid dimension.
     pub fn invalid_dimension(found: usize, expected: usize) -> Self {
         let brief = "Invalid vector dimension.";
@@ -84,6 +125,15 @@ impl From<SledError> for Error {
     }
 }
 
+impl From<TransactionError<()>> for Error {
+    fn from(err: TransactionError<()>) -> Self {
+        match err {
+            TransactionError::Storage(err) => err.into(),
+            TransactionError::Abort(_) => "The transaction is aborted.".into(),
+        }
+    }
+}
+
 impl From<IOError> for Error {
     fn from(err: IOError) -> Self {
         Error(err.to_string())
@@ -0,0 +1,603 @@
+use super::*;
+use std::str::FromStr;
+
+/// The predicate over record metadata used to filter search results.
+/// Field paths are dotted keys into nested metadata objects like
+/// `author.name`. An empty path refers to the metadata itself.
+///
+/// Filters can also be parsed from a string expression:
+///
+/// ```text
+/// author.name == "Alice" and (year >= 2000 or not exists draft)
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// All of the filters match.
+    And(Vec<Filter>),
+    /// Any of the filters match.
+    Or(Vec<Filter>),
+    /// The filter doesn't match.
+    Not(Box<Filter>),
+    /// The value at the path is equal to the metadata.
+    Eq(String, Metadata),
+    /// The value at the path is missing or not equal to the metadata.
+    Ne(String, Metadata),
+    /// The value at the path is greater than the number or text.
+    Gt(String, Metadata),
+    /// The value at the path is less than the number or text.
+    Lt(String, Metadata),
+    /// The value at the path is greater than or equal to
+    /// the number or text.
+    Gte(String, Metadata),
+    /// The value at the path is less than or equal to
+    /// the number or text.
+    Lte(String, Metadata),
+    /// The numeric value at the path is within the inclusive range.
+    /// A missing bound means the range is unbounded on that side.
+    Range(String, Option<f64>, Option<f64>),
+    /// The value at the path is equal to one of the metadata.
+    In(String, Vec<Metadata>),
+    /// The array at the path contains the metadata or the text
+    /// at the path contains the text as a substring.
+    Contains(String, Metadata),
+    /// The path exists in the metadata.
+    Exists(String),
+}
+
+impl Filter {
+    /// Creates a filter matching values equal to the metadata.
+    /// * `path`: Dotted path to the metadata field.
+    /// * `value`: Metadata value to compare with.
+    pub fn eq(path: &str, value: impl Into<Metadata>) -> Self {
+        Filter::Eq(path.to_string(), value.into())
+    }
+
+    /// Creates a filter matching numbers within the inclusive range.
+    /// * `path`: Dotted path to the metadata field.
+    /// * `min`: Lower bound of the range, if any.
+    /// * `max`: Upper bound of the range, if any.
+    pub fn range(path: &str, min: Option<f64>, max: Option<f64>) -> Self {
+        Filter::Range(path.to_string(), min, max)
+    }
+
+    /// Creates a filter matching arrays containing the metadata.
+    /// * `path`: Dotted path to the metadata field.
+    /// * `value`: Metadata value the array should contain.
+    pub fn contains(path: &str, value: impl Into<Metadata>) -> Self {
+        Filter::Contains(path.to_string(), value.into())
+    }
+
+    /// Creates a filter matching metadata where the path exists.
+    /// * `path`: Dotted path to the metadata field.
+    pub fn exists(path: &str) -> Self {
+        Filter::Exists(path.to_string())
+    }
+
+    /// Parses a filter from a string expression.
+    /// * `expression`: Filter expression like `year > 2000`.
+    pub fn parse(expression: &str) -> Result<Self, Error> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let filter = parser.parse_or()?;
+
+        // Make sure the whole expression is consumed.
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => {
+                Err(Error::invalid_filter(&format!("Unexpected {token}")))
+            }
+        }
+    }
+
+    /// Returns true if the metadata satisfies the filter.
+    /// * `data`: Metadata of the record to check.
+    pub fn matches(&self, data: &Metadata) -> bool {
+        match self {
+            Filter::And(filters) => filters.iter().all(|f| f.matches(data)),
+            Filter::Or(filters) => filters.iter().any(|f| f.matches(data)),
+            Filter::Not(filter) => !filter.matches(data),
+            Filter::Eq(path, value) => match data.get(path) {
+                Some(field) => equals(field, value),
+                None => false,
+            },
+            Filter::Ne(path, value) => match data.get(path) {
+                Some(field) => !equals(field, value),
+                None => true,
+            },
+            Filter::Gt(path, value) => {
+                let field = data.get(path);
+                let ordering = field.and_then(|field| compare(field, value));
+                ordering == Some(Ordering::Greater)
+            }
+            Filter::Lt(path, value) => {
+                let field = data.get(path);
+                let ordering = field.and_then(|field| compare(field, value));
+                ordering == Some(Ordering::Less)
+            }
+            Filter::Gte(path, value) => {
+                let field = data.get(path);
+                let ordering = field.and_then(|field| compare(field, value));
+                ordering.is_some_and(|ordering| ordering.is_ge())
+            }
+            Filter::Lte(path, value) => {
+                let field = data.get(path);
+                let ordering = field.and_then(|field| compare(field, value));
+                ordering.is_some_and(|ordering| ordering.is_le())
+            }
+            Filter::Range(path, min, max) => {
+                let number = match data.get(path).and_then(as_number) {
+                    Some(number) => number,
+                    None => return false,
+                };
+
+                let above_min = match min {
+                    Some(min) => number >= *min,
+                    None => true,
+                };
+
+                let below_max = match max {
+                    Some(max) => number <= *max,
+                    None => true,
+                };
+
+                above_min && below_max
+            }
+            Filter::In(path, values) => match data.get(path) {
+                Some(field) => values.iter().any(|v| equals(field, v)),
+                None => false,
+            },
+            Filter::Contains(path, value) => match (data.get(path), value) {
+                (Some(Metadata::Array(arr)), _) => {
+                    arr.iter().any(|item| equals(item, value))
+                }
+                (Some(Metadata::Text(text)), Metadata::Text(sub)) => {
+                    text.contains(sub.as_str())
+                }
+                _ => false,
+            },
+            Filter::Exists(path) => data.get(path).is_some(),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        Filter::parse(expression)
+    }
+}
+
+// This implementation converts a Python string expression or
+// a dictionary into a filter. The dictionary uses the operator
+// as the key like {"eq": {"author.name": "Alice"}}. Multiple
+// keys in a dictionary are combined with the and operator.
+impl TryFrom<&PyAny> for Filter {
+    type Error = Error;
+    fn try_from(value: &PyAny) -> Result<Self, Self::Error> {
+        if let Ok(expression) = value.extract::<String>() {
+            return Filter::parse(&expression);
+        }
+
+        let dict = match value.extract::<HashMap<String, &PyAny>>() {
+            Ok(dict) => dict,
+            Err(_) => {
+                let message = "The filter must be a string or a dictionary.";
+                return Err(message.into());
+            }
+        };
+
+        let mut filters = vec![];
+        for (operator, operand) in dict {
+            filters.extend(from_py_operator(&operator, operand)?);
+        }
+
+        Ok(combine(filters, Filter::And))
+    }
+}
+
+/// Converts a Python dictionary entry into filters.
+fn from_py_operator(
+    operator: &str,
+    operand: &PyAny,
+) -> Result<Vec<Filter>, Error> {
+    // Converts a list of Python filters.
+    let to_filters = |operand: &PyAny| -> Result<Vec<Filter>, Error> {
+        let list = operand.extract::<Vec<&PyAny>>().map_err(py_error)?;
+        list.into_iter().map(Filter::try_from).collect()
+    };
+
+    let filter = match operator {
+        "and" => Filter::And(to_filters(operand)?),
+        "or" => Filter::Or(to_filters(operand)?),
+        "not" => Filter::Not(Box::new(Filter::try_from(operand)?)),
+        "exists" => {
+            let path = operand.extract::<String>().map_err(py_error)?;
+            Filter::Exists(path)
+        }
+        _ => {
+            // Other operators map field paths to their operands.
+            let fields = operand
+                .extract::<HashMap<String, &PyAny>>()
+                .map_err(py_error)?;
+
+            let mut filters = vec![];
+            for (path, value) in fields {
+                filters.push(from_py_field(operator, path, value)?);
+            }
+
+            return Ok(filters);
+        }
+    };
+
+    Ok(vec![filter])
+}
+
+/// Converts a Python field comparison into a filter.
+fn from_py_field(
+    operator: &str,
+    path: String,
+    value: &PyAny,
+) -> Result<Filter, Error> {
+    let filter = match operator {
+        "eq" => Filter::Eq(path, value.into()),
+        "ne" => Filter::Ne(path, value.into()),
+        "gt" => Filter::Gt(path, value.into()),
+        "lt" => Filter::Lt(path, value.into()),
+        "gte" => Filter::Gte(path, value.into()),
+        "lte" => Filter::Lte(path, value.into()),
+        "contains" => Filter::Contains(path, value.into()),
+        "in" => {
+            let list = value.extract::<Vec<&PyAny>>().map_err(py_error)?;
+            Filter::In(path, list.into_iter().map(|v| v.into()).collect())
+        }
+        _ => {
+            let message = format!("Unknown operator: {operator}");
+            return Err(Error::invalid_filter(&message));
+        }
+    };
+
+    Ok(filter)
+}
+
+/// Converts a Python extraction error into an invalid filter error.
+fn py_error(err: PyErr) -> Error {
+    Error::invalid_filter(&err.to_string())
+}
+
+/// Combines filters with the logical operator unless
+/// there is only a single filter.
+fn combine(
+    mut filters: Vec<Filter>,
+    operator: fn(Vec<Filter>) -> Filter,
+) -> Filter {
+    if filters.len() == 1 {
+        return filters.remove(0);
+    }
+
+    operator(filters)
+}
+
+/// Converts numeric metadata to a float for comparison.
+fn as_number(data: &Metadata) -> Option<f64> {
+    match data {
+        Metadata::Integer(int) => Some(*int as f64),
+        Metadata::Float(float) => Some(*float as f64),
+        _ => None,
+    }
+}
+
+/// Compares metadata where integers and floats of the same
+/// value are considered equal.
+fn equals(a: &Metadata, b: &Metadata) -> bool {
+    match (as_number(a), as_number(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Orders numbers by value and texts lexicographically.
+/// Other metadata types are not comparable.
+fn compare(a: &Metadata, b: &Metadata) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (as_number(a), as_number(b)) {
+        return a.partial_cmp(&b);
+    }
+
+    match (a, b) {
+        (Metadata::Text(a), Metadata::Text(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// The token of a filter expression.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A field path or a keyword like `and`.
+    Word(String),
+    /// A quoted text value.
+    Text(String),
+    /// A numeric value.
+    Number(String),
+    /// A symbol like `==` or `(`.
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "'{word}'"),
+            Token::Text(text) => write!(f, "\"{text}\""),
+            Token::Number(number) => write!(f, "{number}"),
+            Token::Symbol(symbol) => write!(f, "'{symbol}'"),
+        }
+    }
+}
+
+/// Symbols of the filter expression sorted so that
+/// the longer symbols are matched first.
+const SYMBOLS: [&str; 12] =
+    ["==", "!=", ">=", "<=", ">", "<", "=", "(", ")", "[", "]", ","];
+
+/// Splits a filter expression into tokens.
+fn tokenize(expression: &str) -> Result<Vec<Token>, Error> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // Quoted text using single or double quotes.
+        if c == '"' || c == '\'' {
+            let start = i + 1;
+            let end = match chars[start..].iter().position(|x| *x == c) {
+                Some(len) => start + len,
+                None => return Err(Error::invalid_filter("Unclosed quote")),
+            };
+
+            tokens.push(Token::Text(chars[start..end].iter().collect()));
+            i = end + 1;
+            continue;
+        }
+
+        // Numbers which may start with a minus sign.
+        if c.is_ascii_digit() || c == '-' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && is_number_char(chars[i]) {
+                i += 1;
+            }
+
+            tokens.push(Token::Number(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        // Field paths and keywords.
+        if is_word_char(c) {
+            let start = i;
+            while i < chars.len() && is_word_char(chars[i]) {
+                i += 1;
+            }
+
+            tokens.push(Token::Word(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        let rest: String = chars[i..].iter().take(2).collect();
+        match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+            Some(symbol) => {
+                tokens.push(Token::Symbol(symbol));
+                i += symbol.len();
+            }
+            None => {
+                let message = format!("Unexpected character '{c}'");
+                return Err(Error::invalid_filter(&message));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+fn is_number_char(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+')
+}
+
+/// Recursive descent parser of the filter expression.
+/// Operator precedence from the lowest: `or`, `and`, `not`.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, Error> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token.ok_or_else(|| {
+            Error::invalid_filter("Unexpected end of expression")
+        })
+    }
+
+    /// Consumes the next token if it's the keyword.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let is_keyword = match self.peek() {
+            Some(Token::Word(word)) => word.eq_ignore_ascii_case(keyword),
+            _ => false,
+        };
+
+        if is_keyword {
+            self.position += 1;
+        }
+
+        is_keyword
+    }
+
+    /// Consumes the next token if it's the symbol.
+    fn symbol(&mut self, symbol: &'static str) -> bool {
+        let is_symbol = self.peek() == Some(&Token::Symbol(symbol));
+        if is_symbol {
+            self.position += 1;
+        }
+
+        is_symbol
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), Error> {
+        if self.symbol(symbol) {
+            return Ok(());
+        }
+
+        let message = format!("Expected '{symbol}'");
+        Err(Error::invalid_filter(&message))
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, Error> {
+        let mut filters = vec![self.parse_and()?];
+        while self.keyword("or") {
+            filters.push(self.parse_and()?);
+        }
+
+        Ok(combine(filters, Filter::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, Error> {
+        let mut filters = vec![self.parse_unary()?];
+        while self.keyword("and") {
+            filters.push(self.parse_unary()?);
+        }
+
+        Ok(combine(filters, Filter::And))
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, Error> {
+        if self.keyword("not") {
+            let filter = self.parse_unary()?;
+            return Ok(Filter::Not(Box::new(filter)));
+        }
+
+        if self.keyword("exists") {
+            let path = self.parse_path()?;
+            return Ok(Filter::Exists(path));
+        }
+
+        if self.symbol("(") {
+            let filter = self.parse_or()?;
+            self.expect(")")?;
+            return Ok(filter);
+        }
+
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Filter, Error> {
+        let path = self.parse_path()?;
+
+        if self.keyword("in") {
+            let values = match self.parse_value()? {
+                Metadata::Array(values) => values,
+                _ => {
+                    return Err(Error::invalid_filter(
+                        "Expected a list after 'in'",
+                    ))
+                }
+            };
+
+            return Ok(Filter::In(path, values));
+        }
+
+        if self.keyword("contains") {
+            let value = self.parse_value()?;
+            return Ok(Filter::Contains(path, value));
+        }
+
+        let operator = match self.next()? {
+            Token::Symbol(symbol) => symbol,
+            token => {
+                let message = format!("Expected an operator, found {token}");
+                return Err(Error::invalid_filter(&message));
+            }
+        };
+
+        let value = self.parse_value()?;
+        let filter = match operator {
+            "==" | "=" => Filter::Eq(path, value),
+            "!=" => Filter::Ne(path, value),
+            ">" => Filter::Gt(path, value),
+            "<" => Filter::Lt(path, value),
+            ">=" => Filter::Gte(path, value),
+            "<=" => Filter::Lte(path, value),
+            _ => {
+                let message = format!("Unknown operator '{operator}'");
+                return Err(Error::invalid_filter(&message));
+            }
+        };
+
+        Ok(filter)
+    }
+
+    fn parse_path(&mut self) -> Result<String, Error> {
+        match self.next()? {
+            Token::Word(path) => Ok(path),
+            token => {
+                let message = format!("Expected a field path, found {token}");
+                Err(Error::invalid_filter(&message))
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Metadata, Error> {
+        let token = self.next()?;
+        match token {
+            Token::Text(text) => Ok(Metadata::Text(text)),
+            Token::Number(number) => parse_number(&number),
+            Token::Symbol("[") => {
+                let mut values = vec![];
+                if self.symbol("]") {
+                    return Ok(Metadata::Array(values));
+                }
+
+                loop {
+                    values.push(self.parse_value()?);
+                    if self.symbol("]") {
+                        break;
+                    }
+
+                    self.expect(",")?;
+                }
+
+                Ok(Metadata::Array(values))
+            }
+            token => {
+                let message = format!("Expected a value, found {token}");
+                Err(Error::invalid_filter(&message))
+            }
+        }
+    }
+}
+
+/// Parses a number as an integer when possible or a float.
+fn parse_number(number: &str) -> Result<Metadata, Error> {
+    if let Ok(int) = number.parse::<usize>() {
+        return Ok(Metadata::Integer(int));
+    }
+
+    match number.parse::<f32>() {
+        Ok(float) => Ok(Metadata::Float(float)),
+        Err(_) => {
+            Err(Error::invalid_filter(&format!("Invalid number {number}")))
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use super::*;
+use std::ops::Bound;
+
+/// The type of values indexed for a metadata field.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash)]
+pub enum IndexType {
+    /// Text values like categories or tenant names.
+    Keyword,
+    /// Integer values like years or external IDs.
+    Integer,
+    /// Float values like scores or prices.
+    Float,
+}
+
+impl IndexType {
+    /// Creates a new index type from a string.
+    /// Available options:
+    /// * `keyword`: Text values.
+    /// * `integer`: Integer values.
+    /// * `float`: Float values.
+    pub fn from(kind: &str) -> Result<Self, Error> {
+        match kind {
+            "keyword" => Ok(IndexType::Keyword),
+            "integer" => Ok(IndexType::Integer),
+            "float" => Ok(IndexType::Float),
+            _ => Err("Index type not supported.".into()),
+        }
+    }
+}
+
+impl IntoPy<Py<PyAny>> for IndexType {
+    fn into_py(self, py: Python) -> Py<PyAny> {
+        match self {
+            IndexType::Keyword => "keyword".into_py(py),
+            IndexType::Integer => "integer".into_py(py),
+            IndexType::Float => "float".into_py(py),
+        }
+    }
+}
+
+/// The key of an indexed metadata value.
+/// An index only contains keys of the same variant.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum IndexKey {
+    Text(String),
+    Integer(usize),
+    Float(OrderedFloat<f32>),
+}
+
+/// The secondary index over values of a metadata field.
+///
+/// Array values are indexed by their elements. Values that can't
+/// be converted to the index type are tracked separately and are
+/// always included in the lookups so the lookups never miss a
+/// record that might match the filter.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FieldIndex {
+    kind: IndexType,
+    keys: BTreeMap<IndexKey, HashSet<VectorID>>,
+    others: HashSet<VectorID>,
+}
+
+impl FieldIndex {
+    /// Creates an empty index of the given type.
+    pub fn new(kind: IndexType) -> Self {
+        Self { kind, keys: BTreeMap::new(), others: HashSet::new() }
+    }
+
+    /// Returns the type of the indexed values.
+    pub fn kind(&self) -> IndexType {
+        self.kind
+    }
+
+    /// Adds the field value of a record to the index.
+    /// * `id`: Vector ID of the record.
+    /// * `value`: Value of the indexed field, if it exists.
+    pub fn insert(&mut self, id: &VectorID, value: Option<&Metadata>) {
+        for item in Self::items(value) {
+            match self.key(item) {
+                Some(key) => self.keys.entry(key).or_default().insert(*id),
+                None => self.others.insert(*id),
+            };
+        }
+    }
+
+    /// Removes the field value of a record from the index.
+    /// * `id`: Vector ID of the record.
+    /// * `value`: Value of the indexed field, if it exists.
+    pub fn remove(&mut self, id: &VectorID, value: Option<&Metadata>) {
+        for item in Self::items(value) {
+            let key = match self.key(item) {
+                Some(key) => key,
+                None => {
+                    self.others.remove(id);
+                    continue;
+                }
+            };
+
+            if let Some(ids) = self.keys.get_mut(&key) {
+                ids.remove(id);
+                if ids.is_empty() {
+                    self.keys.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Returns the vector IDs that might match the filter on
+    /// the indexed field or None if the index can't help.
+    /// * `filter`: Filter on the indexed field path.
+    pub fn lookup(&self, filter: &Filter) -> Option<HashSet<VectorID>> {
+        let ids = match filter {
+            Filter::Eq(_, value) => self.lookup_eq(value)?,
+            Filter::In(_, values) => {
+                let mut ids = HashSet::new();
+                for value in values {
+                    ids.extend(self.lookup_eq(value)?);
+                }
+
+                ids
+            }
+            Filter::Contains(_, value) => {
+                // Text fields contain substrings which can't be
+                // found by the keyword lookup.
+                let is_text = matches!(value, Metadata::Text(_));
+                if self.kind == IndexType::Keyword && is_text {
+                    return None;
+                }
+
+                self.lookup_eq(value)?
+            }
+            Filter::Gt(_, value) => self.lookup_compare(value, true, false),
+            Filter::Lt(_, value) => self.lookup_compare(value, false, false),
+            Filter::Gte(_, value) => self.lookup_compare(value, true, true),
+            Filter::Lte(_, value) => self.lookup_compare(value, false, true),
+            Filter::Range(_, min, max) => self.lookup_range(*min, *max),
+            _ => return None,
+        };
+
+        Some(ids)
+    }
+
+    /// Returns the values to index for the field value.
+    fn items(value: Option<&Metadata>) -> Vec<&Metadata> {
+        match value {
+            Some(Metadata::Array(arr)) => arr.iter().collect(),
+            Some(value) => vec![value],
+            None => vec![],
+        }
+    }
+
+    /// Converts the metadata value into the index key.
+    fn key(&self, value: &Metadata) -> Option<IndexKey> {
+        match (self.kind, value) {
+            (IndexType::Keyword, Metadata::Text(text)) => {
+                Some(IndexKey::Text(text.clone()))
+            }
+            (IndexType::Integer, Metadata::Integer(int)) => {
+                Some(IndexKey::Integer(*int))
+            }
+            (IndexType::Integer, Metadata::Float(float)) => {
+                // Only whole floats are equal to an integer.
+                let is_whole = float.fract() == 0.0 && *float >= 0.0;
+                is_whole.then_some(IndexKey::Integer(*float as usize))
+            }
+            (IndexType::Float, Metadata::Integer(int)) => {
+                Some(IndexKey::Float((*int as f32).into()))
+            }
+            (IndexType::Float, Metadata::Float(float)) => {
+                Some(IndexKey::Float((*float).into()))
+            }
+            _ => None,
+        }
+    }
+
+    fn lookup_eq(&self, value: &Metadata) -> Option<HashSet<VectorID>> {
+        // Arrays are indexed by elements so they can't be
+        // compared with the array value as a whole.
+        if let Metadata::Array(_) = value {
+            return None;
+        }
+
+        let mut ids = self.others.clone();
+        if let Some(matches) = self.key(value).and_then(|k| self.keys.get(&k)) {
+            ids.extend(matches);
+        }
+
+        Some(ids)
+    }
+
+    fn lookup_compare(
+        &self,
+        value: &Metadata,
+        greater: bool,
+        inclusive: bool,
+    ) -> HashSet<VectorID> {
+        let bound = match (self.kind, value) {
+            (IndexType::Keyword, Metadata::Text(text)) => {
+                let key = IndexKey::Text(text.clone());
+                match inclusive {
+                    true => Some(Bound::Included(key)),
+                    false => Some(Bound::Excluded(key)),
+                }
+            }
+            (IndexType::Keyword, _) => None,
+            (_, value) => {
+                let number = match value {
+                    Metadata::Integer(int) => Some(*int as f64),
+                    Metadata::Float(float) => Some(*float as f64),
+                    _ => None,
+                };
+
+                number.and_then(|number| match inclusive {
+                    true => self.bound_inclusive(number, greater),
+                    false => self.bound(number, greater),
+                })
+            }
+        };
+
+        match (bound, greater) {
+            (Some(bound), true) => self.collect((bound, Bound::Unbounded)),
+            (Some(bound), false) => self.collect((Bound::Unbounded, bound)),
+            (None, _) => self.others.clone(),
+        }
+    }
+
+    fn lookup_range(
+        &self,
+        min: Option<f64>,
+        max: Option<f64>,
+    ) -> HashSet<VectorID> {
+        if self.kind == IndexType::Keyword {
+            return self.others.clone();
+        }
+
+        let lower = min.map_or(Some(Bound::Unbounded), |min| {
+            self.bound_inclusive(min, true)
+        });
+
+        let upper = max.map_or(Some(Bound::Unbounded), |max| {
+            self.bound_inclusive(max, false)
+        });
+
+        match (lower, upper) {
+            (Some(lower), Some(upper)) => self.collect((lower, upper)),
+            _ => self.others.clone(),
+        }
+    }
+
+    /// Returns the exclusive bound of a numeric comparison.
+    /// None means no indexed key can satisfy the comparison.
+    fn bound(&self, value: f64, greater: bool) -> Option<Bound<IndexKey>> {
+        match self.kind {
+            // Include the rounded float bound so floats that
+            // are equal after rounding to f32 are not missed.
+            IndexType::Float => {
+                Some(Bound::Included(IndexKey::Float((value as f32).into())))
+            }
+            _ if greater => match value < 0.0 {
+                true => Some(Bound::Unbounded),
+                false => {
+                    let key = IndexKey::Integer(value.floor() as usize);
+                    Some(Bound::Excluded(key))
+                }
+            },
+            _ => match value <= 0.0 {
+                true => None,
+                false => {
+                    let key = IndexKey::Integer(value.ceil() as usize);
+                    Some(Bound::Excluded(key))
+                }
+            },
+        }
+    }
+
+    /// Returns the inclusive bound of a numeric range.
+    /// None means no indexed key can satisfy the range.
+    fn bound_inclusive(
+        &self,
+        value: f64,
+        lower: bool,
+    ) -> Option<Bound<IndexKey>> {
+        match self.kind {
+            IndexType::Float => {
+                Some(Bound::Included(IndexKey::Float((value as f32).into())))
+            }
+            _ if lower => match value <= 0.0 {
+                true => Some(Bound::Unbounded),
+                false => {
+                    let key = IndexKey::Integer(value.ceil() as usize);
+                    Some(Bound::Included(key))
+                }
+            },
+            _ => match value < 0.0 {
+                true => None,
+                false => {
+                    let key = IndexKey::Integer(value.floor() as usize);
+                    Some(Bound::Included(key))
+                }
+            },
+        }
+    }
+
+    /// Collects the vector IDs of the keys within the range.
+    fn collect(
+        &self,
+        range: (Bound<IndexKey>, Bound<IndexKey>),
+    ) -> HashSet<VectorID> {
+        let mut ids = self.others.clone();
+
+        // BTreeMap panics if the range start is after its end.
+        let is_valid = match (&range.0, &range.1) {
+            (Bound::Included(a), Bound::Included(b)) => a <= b,
+            (Bound::Included(a) | Bound::Excluded(a), Bound::Excluded(b))
+            | (Bound::Excluded(a), Bound::Included(b)) => a < b,
+            _ => true,
+        };
+
+        if is_valid {
+            for (_, matches) in self.keys.range(range) {
+                ids.extend(matches);
+            }
+        }
+
+        ids
+    }
+}
+
+/// Returns the vector IDs that might match the filter using the
+/// field indexes or None if the indexes can't narrow it down.
+/// * `indexes`: Field indexes keyed by the metadata field path.
+/// * `filter`: Filter to find the candidates for.
+pub(crate) fn plan(
+    indexes: &HashMap<String, FieldIndex>,
+    filter: &Filter,
+) -> Option<HashSet<VectorID>> {
+    match filter {
+        Filter::And(filters) => {
+            let mut sets = filters.iter().filter_map(|f| plan(indexes, f));
+            let first = sets.next()?;
+            Some(sets.fold(first, |a, b| &a & &b))
+        }
+        Filter::Or(filters) => {
+            let mut ids = HashSet::new();
+            for filter in filters {
+                ids.extend(plan(indexes, filter)?);
+            }
+
+            Some(ids)
+        }
+        Filter::Eq(path, _)
+        | Filter::Gt(path, _)
+        | Filter::Lt(path, _)
+        | Filter::Gte(path, _)
+        | Filter::Lte(path, _)
+        | Filter::Range(path, _, _)
+        | Filter::In(path, _)
+        | Filter::Contains(path, _) => indexes.get(path)?.lookup(filter),
+        _ => None,
+    }
+}
+
+/// Adds the record metadata to the field indexes.
+/// * `indexes`: Field indexes keyed by the metadata field path.
+/// * `id`: Vector ID of the record.
+/// * `data`: Metadata of the record.
+pub(crate) fn index_record(
+    indexes: &mut HashMap<String, FieldIndex>,
+    id: &VectorID,
+    data: &Metadata,
+) {
+    for (path, index) in indexes.iter_mut() {
+        index.insert(id, data.get(path));
+    }
+}
+
+/// Removes the record metadata from the field indexes.
+/// * `indexes`: Field indexes keyed by the metadata field path.
+/// * `id`: Vector ID of the record.
+/// * `data`: Metadata of the record.
+pub(crate) fn unindex_record(
+    indexes: &mut HashMap<String, FieldIndex>,
+    id: &VectorID,
+    data: &Metadata,
+) {
+    for (path, index) in indexes.iter_mut() {
+        index.remove(id, data.get(path));
+    }
+}
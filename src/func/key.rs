@@ -0,0 +1,94 @@
+use super::*;
+
+/// The key assigned to a vector record by the caller.
+/// Unlike the vector ID, the key is chosen by the caller and
+/// can be used to address the record from external systems.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RecordKey {
+    /// A text key like UUID or document path.
+    Text(String),
+    /// An integer key like an external database ID.
+    Integer(u64),
+}
+
+impl From<String> for RecordKey {
+    fn from(value: String) -> Self {
+        RecordKey::Text(value)
+    }
+}
+
+impl From<&str> for RecordKey {
+    fn from(value: &str) -> Self {
+        RecordKey::Text(value.to_string())
+    }
+}
+
+impl From<u64> for RecordKey {
+    fn from(value: u64) -> Self {
+        RecordKey::Integer(value)
+    }
+}
+
+impl FromPyObject<'_> for RecordKey {
+    fn extract(value: &PyAny) -> PyResult<Self> {
+        if let Ok(text) = value.extract::<String>() {
+            return Ok(RecordKey::Text(text));
+        }
+
+        if let Ok(int) = value.extract::<u64>() {
+            return Ok(RecordKey::Integer(int));
+        }
+
+        let message = "The record key must be a string or an integer.";
+        Err(Error::new(message).into())
+    }
+}
+
+impl IntoPy<Py<PyAny>> for RecordKey {
+    fn into_py(self, py: Python) -> Py<PyAny> {
+        match self {
+            RecordKey::Text(text) => text.into_py(py),
+            RecordKey::Integer(int) => int.into_py(py),
+        }
+    }
+}
+
+/// Validates that the keys of the records are not used yet
+/// and that the records don't share the same key.
+/// * `key_ids`: Vector IDs of the existing keys.
+/// * `records`: Records to insert.
+pub(crate) fn validate_keys(
+    key_ids: &HashMap<RecordKey, VectorID>,
+    records: &[Record],
+) -> Result<(), Error> {
+    let mut keys = HashSet::new();
+    for key in records.iter().filter_map(|record| record.key.as_ref()) {
+        if key_ids.contains_key(key) || !keys.insert(key) {
+            return Err(Error::key_exists());
+        }
+    }
+
+    Ok(())
+}
+
+/// Assigns the key to the record or removes its key if None.
+/// * `keys`: Keys of the vector IDs.
+/// * `key_ids`: Vector IDs of the keys.
+/// * `id`: Vector ID of the record.
+/// * `key`: New key of the record.
+pub(crate) fn set_key(
+    keys: &mut HashMap<VectorID, RecordKey>,
+    key_ids: &mut HashMap<RecordKey, VectorID>,
+    id: &VectorID,
+    key: Option<&RecordKey>,
+) {
+    if let Some(old) = keys.remove(id) {
+        key_ids.remove(&old);
+    }
+
+    if let Some(key) = key {
+        keys.insert(*id, key.clone());
+        key_ids.insert(key.clone(), *id);
+    }
+}
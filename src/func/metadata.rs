@@ -15,6 +15,27 @@ pub enum Metadata {
     Object(HashMap<String, Metadata>),
 }
 
+impl Metadata {
+    /// Returns the nested metadata at the dotted path.
+    /// * `path`: Path of object keys like `author.name`.
+    pub fn get(&self, path: &str) -> Option<&Metadata> {
+        // An empty path refers to the metadata itself.
+        if path.is_empty() {
+            return Some(self);
+        }
+
+        let mut current = self;
+        for key in path.split('.') {
+            current = match current {
+                Metadata::Object(obj) => obj.get(key)?,
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+}
+
 impl From<usize> for Metadata {
     fn from(value: usize) -> Self {
         Metadata::Integer(value)
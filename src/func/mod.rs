@@ -1,23 +1,45 @@
 /// The collection of vectors and their data.
 pub mod collection;
+/// The collection that can be shared across threads.
+pub mod concurrent;
 /// Enum for the collection distance functions.
 pub mod distance;
 /// Error types for the database.
 pub mod err;
+/// Filters for the record metadata.
+pub mod filter;
+/// Secondary indexes for the metadata fields.
+pub mod index;
+/// Types for the record keys.
+pub mod key;
 /// Types for the metadata.
 pub mod metadata;
+/// Quantization to compress the stored vectors.
+pub mod quantization;
+/// SIMD kernels for the distance functions.
+pub mod simd;
+/// Sparse vectors and their fusion with the dense search.
+pub mod sparse;
 /// Types for the vectors.
 pub mod vector;
 
 // Internal modules.
-mod utils;
+pub(crate) mod utils;
+pub(crate) mod view;
 
 use collection::*;
 use distance::*;
 use err::*;
+use filter::Filter;
+use index::*;
+use key::*;
 use metadata::*;
+use quantization::*;
+use simd::Kernel;
+use sparse::*;
 use utils::*;
 use vector::*;
+use view::*;
 
 // External dependencies.
 use ordered_float::OrderedFloat;
@@ -26,9 +48,9 @@ use pyo3::prelude::*;
 use rand::random;
 use rayon::iter::*;
 use serde::{Deserialize, Serialize};
-use serde_big_array::BigArray;
+use std::borrow::Cow;
 use std::cmp::*;
-use std::collections::{BinaryHeap, HashMap};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::ops::{Deref, Index};
 
 // This code is inspired by the HNSW implementation in the
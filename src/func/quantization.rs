@@ -0,0 +1,764 @@
+use super::*;
+use rand::seq::index::sample;
+use std::ops::Range;
+
+/// The quantization used to compress the stored vectors.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash)]
+pub enum Quantization {
+    /// 8-bit integers with a scale and offset per dimension.
+    Scalar,
+    /// 8-bit integers with a scale and offset per vector.
+    ScalarPerVector,
+    /// Centroid indexes of the sub-vectors in trained codebooks.
+    Product,
+    /// Sign bits of the values packed into bytes.
+    Binary,
+}
+
+impl Quantization {
+    /// Creates a new quantization from a string.
+    /// Available options:
+    /// * `scalar`: 8-bit integers scaled per dimension.
+    /// * `scalar_per_vector`: 8-bit integers scaled per vector.
+    /// * `product`: Product quantization with trained codebooks.
+    /// * `binary`: Sign bits of the values.
+    pub fn from(quantization: &str) -> Result<Self, Error> {
+        match quantization {
+            "scalar" => Ok(Quantization::Scalar),
+            "scalar_per_vector" => Ok(Quantization::ScalarPerVector),
+            "product" => Ok(Quantization::Product),
+            "binary" => Ok(Quantization::Binary),
+            _ => Err("Quantization not supported.".into()),
+        }
+    }
+}
+
+impl IntoPy<Py<PyAny>> for Quantization {
+    fn into_py(self, py: Python) -> Py<PyAny> {
+        match self {
+            Quantization::Scalar => "scalar".into_py(py),
+            Quantization::ScalarPerVector => "scalar_per_vector".into_py(py),
+            Quantization::Product => "product".into_py(py),
+            Quantization::Binary => "binary".into_py(py),
+        }
+    }
+}
+
+/// The highest level of a quantized value.
+const LEVELS: f32 = u8::MAX as f32;
+
+/// The part of the range added to both ends of the per-dimension
+/// scales so the values of later vectors are rarely clamped.
+const MARGIN: f32 = 0.25;
+
+/// The number of stored vectors the per-dimension scales are
+/// fitted to when the vectors are inserted one by one.
+pub(crate) const FIT_SAMPLE: usize = 1_000;
+
+/// The range of the values mapped to the quantized levels.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Scale {
+    /// The value of the lowest level.
+    pub offset: f32,
+    /// The value difference between two levels.
+    pub step: f32,
+}
+
+impl Scale {
+    /// Creates a scale covering the values from min to max.
+    pub fn new(min: f32, max: f32) -> Self {
+        Self { offset: min, step: (max - min) / LEVELS }
+    }
+
+    /// Returns the largest value covered by the scale.
+    pub fn max(&self) -> f32 {
+        self.offset + self.step * LEVELS
+    }
+
+    /// Quantizes the value to the nearest level.
+    pub fn encode(&self, value: f32) -> u8 {
+        if self.step == 0.0 {
+            return 0;
+        }
+
+        let level = ((value - self.offset) / self.step).round();
+        level.clamp(0.0, LEVELS) as u8
+    }
+
+    /// Returns the value of the level.
+    pub fn decode(&self, level: u8) -> f32 {
+        self.offset + self.step * level as f32
+    }
+}
+
+/// The quantized vector.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Code {
+    /// The quantized level of each dimension, the centroid index
+    /// of each sub-vector for product quantization, or the packed
+    /// sign bits for binary quantization.
+    pub values: Vec<u8>,
+    /// The scale of the vector if it's quantized per vector.
+    pub scale: Option<Scale>,
+}
+
+/// The scalar quantizer fitted to the stored vectors.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScalarQuantizer {
+    /// The quantization mode.
+    pub kind: Quantization,
+    /// The scale of each dimension if quantized per dimension.
+    pub scales: Vec<Scale>,
+}
+
+impl ScalarQuantizer {
+    /// Creates a quantizer that is not fitted to any vectors yet.
+    pub fn new(kind: Quantization) -> Self {
+        Self { kind, scales: vec![] }
+    }
+
+    /// Returns true if the vectors can be quantized. The scales
+    /// per dimension have to be fitted first.
+    pub fn is_fitted(&self) -> bool {
+        self.kind != Quantization::Scalar || !self.scales.is_empty()
+    }
+
+    /// Fits the per-dimension scales to the vectors if they're not
+    /// fitted yet. The scales are fixed after and the values out of
+    /// them are clamped, so the stored vectors are never quantized
+    /// again when new vectors are inserted.
+    /// * `vectors`: Vectors to fit the scales to.
+    pub fn fit<'a>(&mut self, vectors: impl Iterator<Item = &'a Vector>) {
+        if self.is_fitted() {
+            return;
+        }
+
+        let mut bounds: Vec<(f32, f32)> = vec![];
+        for vector in vectors {
+            if bounds.is_empty() {
+                bounds = vector.0.iter().map(|v| (*v, *v)).collect();
+            }
+
+            for ((min, max), value) in bounds.iter_mut().zip(vector.0.iter()) {
+                *min = min.min(*value);
+                *max = max.max(*value);
+            }
+        }
+
+        let with_margin = |&(min, max): &(f32, f32)| {
+            let margin = (max - min) * MARGIN;
+            Scale::new(min - margin, max + margin)
+        };
+
+        self.scales = bounds.iter().map(with_margin).collect();
+    }
+
+    /// Quantizes the vector.
+    /// * `vector`: Vector to quantize.
+    pub fn encode(&self, vector: &Vector) -> Code {
+        if self.kind == Quantization::Scalar {
+            let iter = vector.0.iter().zip(self.scales.iter());
+            let levels = iter.map(|(value, scale)| scale.encode(*value));
+            return Code { values: levels.collect(), scale: None };
+        }
+
+        let min = vector.0.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = vector.0.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let scale = Scale::new(min, max);
+        let levels = vector.0.iter().map(|value| scale.encode(*value));
+        Code { values: levels.collect(), scale: Some(scale) }
+    }
+
+    /// Returns the approximate values of the quantized vector.
+    /// * `code`: Quantized vector.
+    pub fn decode<'a>(
+        &'a self,
+        code: &'a Code,
+    ) -> impl Iterator<Item = f32> + 'a {
+        let levels = code.values.iter().enumerate();
+        levels.map(move |(i, level)| match &code.scale {
+            Some(scale) => scale.decode(*level),
+            None => self.scales[i].decode(*level),
+        })
+    }
+
+    /// Folds the scales into the query so the distances are
+    /// calculated on the quantized levels. Returns None for the
+    /// distance functions other than the dot product and the
+    /// Euclidean distances.
+    /// * `distance`: Distance function to use.
+    /// * `vector`: Query vector.
+    pub fn table(
+        &self,
+        distance: &Distance,
+        vector: &Vector,
+    ) -> Option<ScalarTable> {
+        let euclidean = match distance {
+            Distance::Dot => false,
+            Distance::Euclidean | Distance::SquaredEuclidean => true,
+            _ => return None,
+        };
+
+        let query = vector.0.iter().copied();
+        let (values, steps, bias) = match self.kind {
+            // The per-vector scales are folded in for each code.
+            Quantization::ScalarPerVector => {
+                (vector.0.clone(), vec![], vector.0.iter().sum())
+            }
+            // The offsets are subtracted from the query.
+            _ if euclidean => {
+                let iter = query.zip(self.scales.iter());
+                let values = iter.map(|(q, s)| q - s.offset).collect();
+                let steps = self.scales.iter().map(|s| s.step).collect();
+                (values, steps, 0.0)
+            }
+            // The dot product with the offsets is a constant.
+            _ => {
+                let iter = query.zip(self.scales.iter());
+                let values = iter.clone().map(|(q, s)| q * s.step).collect();
+                let bias = iter.map(|(q, s)| q * s.offset).sum();
+                (values, vec![], bias)
+            }
+        };
+
+        let norm = vector.0.iter().map(|x| x * x).sum();
+        let distance = distance.clone();
+        Some(ScalarTable { distance, values, steps, bias, norm })
+    }
+
+    /// Calculates the distance to the quantized vector
+    /// using the folded scales of the query.
+    /// * `table`: Query with the scales folded in.
+    /// * `code`: Quantized vector.
+    pub fn distance(&self, table: &ScalarTable, code: &Code) -> f32 {
+        let levels = code.values.as_slice();
+        let squared = match (&code.scale, &table.distance) {
+            (None, Distance::Dot) => {
+                return table.bias + dot_levels(&table.values, levels);
+            }
+            (None, _) => squared_levels(&table.values, &table.steps, levels),
+            (Some(scale), distance) => {
+                let (offset, step) = (scale.offset, scale.step);
+                let dot = dot_levels(&table.values, levels);
+                if *distance == Distance::Dot {
+                    return offset * table.bias + step * dot;
+                }
+
+                // Expanded (q - offset - step * level)^2 summed up.
+                let sum: u32 = levels.iter().map(|l| *l as u32).sum();
+                let sum_sq: u32 =
+                    levels.iter().map(|l| (*l as u32).pow(2)).sum();
+                let len = levels.len() as f32;
+                let value = table.norm - 2.0 * offset * table.bias
+                    + len * offset * offset
+                    - 2.0 * step * dot
+                    + 2.0 * offset * step * sum as f32
+                    + step * step * sum_sq as f32;
+                value.max(0.0)
+            }
+        };
+
+        match table.distance {
+            Distance::Euclidean => squared.sqrt(),
+            _ => squared,
+        }
+    }
+}
+
+/// The query of the scalar quantizer with the scales folded in.
+#[derive(Debug, Clone)]
+pub struct ScalarTable {
+    distance: Distance,
+    /// The query values multiplied by the steps for the dot product
+    /// or minus the offsets for the Euclidean distance. The values
+    /// are unchanged if the vectors are scaled per vector.
+    values: Vec<f32>,
+    /// The steps of the dimensions for the Euclidean distance.
+    steps: Vec<f32>,
+    /// The dot product of the query and the offsets or the sum
+    /// of the query values if the vectors are scaled per vector.
+    bias: f32,
+    /// The squared norm of the query.
+    norm: f32,
+}
+
+/// The number of lanes the level sums are accumulated in
+/// so the compiler can vectorize the loops.
+const LANES: usize = 8;
+
+/// Returns the dot product of the values and the levels.
+fn dot_levels(values: &[f32], levels: &[u8]) -> f32 {
+    let mut sums = [0.0; LANES];
+    let values_chunks = values.chunks_exact(LANES);
+    let levels_chunks = levels.chunks_exact(LANES);
+    let rest = values_chunks.remainder().iter().zip(levels_chunks.remainder());
+    for (values, levels) in values_chunks.zip(levels_chunks) {
+        let lanes = sums.iter_mut().zip(values.iter().zip(levels));
+        for (sum, (value, level)) in lanes {
+            *sum += value * *level as f32;
+        }
+    }
+
+    let rest: f32 = rest.map(|(value, level)| value * *level as f32).sum();
+    sums.iter().sum::<f32>() + rest
+}
+
+/// Returns the squared Euclidean distance of the values and the
+/// levels multiplied by the steps.
+fn squared_levels(values: &[f32], steps: &[f32], levels: &[u8]) -> f32 {
+    let mut sums = [0.0; LANES];
+    let chunks = values
+        .chunks_exact(LANES)
+        .zip(steps.chunks_exact(LANES))
+        .zip(levels.chunks_exact(LANES));
+
+    for ((values, steps), levels) in chunks {
+        let lanes = sums.iter_mut().zip(values.iter().zip(steps).zip(levels));
+        for (sum, ((value, step), level)) in lanes {
+            *sum += (value - step * *level as f32).powi(2);
+        }
+    }
+
+    let start = values.len() / LANES * LANES;
+    let rest =
+        values[start..].iter().zip(&steps[start..]).zip(&levels[start..]);
+    let rest: f32 = rest.map(|((v, s), l)| (v - s * *l as f32).powi(2)).sum();
+    sums.iter().sum::<f32>() + rest
+}
+
+/// The maximum number of centroids in each codebook so the
+/// centroid indexes fit in a byte.
+const CENTROIDS: usize = 256;
+
+/// The maximum number of vectors used to train the codebooks.
+const TRAINING_SAMPLE: usize = 10_000;
+
+/// The number of k-means iterations to train the codebooks.
+const ITERATIONS: usize = 10;
+
+/// The product quantizer that splits the vectors into sub-vectors
+/// and quantizes each of them to the nearest centroid of its
+/// codebook trained with k-means.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ProductQuantizer {
+    /// The number of sub-vectors to split the vectors into.
+    pub subvectors: usize,
+    /// The vector dimension the codebooks are trained for.
+    pub dimension: usize,
+    /// The centroids of each sub-vector concatenated.
+    pub codebooks: Vec<Vec<f32>>,
+    /// The squared norms of the centroids of each sub-vector.
+    pub norms: Vec<Vec<f32>>,
+}
+
+impl ProductQuantizer {
+    /// Creates a product quantizer that is not trained yet.
+    /// * `subvectors`: Number of sub-vectors to split vectors into.
+    pub fn new(subvectors: usize) -> Self {
+        let subvectors = subvectors.max(1);
+        Self { subvectors, dimension: 0, codebooks: vec![], norms: vec![] }
+    }
+
+    /// Returns true if the codebooks are trained.
+    pub fn is_trained(&self) -> bool {
+        !self.codebooks.is_empty()
+    }
+
+    /// Trains the codebooks with k-means on a sample of the vectors.
+    /// * `vectors`: Vectors to train on.
+    pub fn train(&mut self, vectors: &[&Vector]) -> Result<(), Error> {
+        if vectors.is_empty() {
+            return Err("The quantizer requires vectors to train.".into());
+        }
+
+        let sample: Vec<&Vector> = match vectors.len() > TRAINING_SAMPLE {
+            true => {
+                let mut rng = rand::thread_rng();
+                let indexes = sample(&mut rng, vectors.len(), TRAINING_SAMPLE);
+                indexes.into_iter().map(|i| vectors[i]).collect()
+            }
+            false => vectors.to_vec(),
+        };
+
+        // Each sub-vector needs at least one dimension.
+        self.dimension = sample[0].len();
+        self.subvectors = self.subvectors.min(self.dimension).max(1);
+        let k = CENTROIDS.min(sample.len());
+
+        self.codebooks = (0..self.subvectors)
+            .map(|i| {
+                let range = self.range(i);
+                let points: Vec<&[f32]> =
+                    sample.iter().map(|v| &v.0[range.clone()]).collect();
+                kmeans(&points, k, range.len())
+            })
+            .collect();
+
+        self.norms = self
+            .codebooks
+            .iter()
+            .zip(0..self.subvectors)
+            .map(|(codebook, i)| {
+                let len = self.range(i).len();
+                let centroids = codebook.chunks(len.max(1)).take(k);
+                centroids.map(|c| c.iter().map(|x| x * x).sum()).collect()
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    /// Quantizes the vector to the nearest centroid indexes.
+    /// * `vector`: Vector to quantize.
+    pub fn encode(&self, vector: &Vector) -> Code {
+        let values = (0..self.subvectors).map(|i| {
+            let sub = &vector.0[self.range(i)];
+            let distances = self.centroids(i).map(|c| squared(sub, c));
+            let nearest =
+                distances.enumerate().min_by(|a, b| a.1.total_cmp(&b.1));
+            nearest.map_or(0, |(index, _)| index as u8)
+        });
+
+        Code { values: values.collect(), scale: None }
+    }
+
+    /// Returns the approximate vector of the centroids.
+    /// * `code`: Quantized vector.
+    pub fn decode(&self, code: &Code) -> Vector {
+        let mut vector = Vec::with_capacity(self.dimension);
+        for (i, index) in code.values.iter().enumerate() {
+            let len = self.range(i).len();
+            let start = *index as usize * len;
+            vector.extend_from_slice(&self.codebooks[i][start..start + len]);
+        }
+
+        vector.into()
+    }
+
+    /// Calculates the distances between the sub-vectors of the
+    /// query and the centroids so the distance to a quantized
+    /// vector is a sum or max of lookups. Returns None if the
+    /// distance can't be split by sub-vectors like Jaccard or
+    /// the custom distance functions.
+    /// * `distance`: Distance function to use.
+    /// * `vector`: Query vector.
+    pub fn table(
+        &self,
+        distance: &Distance,
+        vector: &Vector,
+    ) -> Option<ProductTable> {
+        if matches!(distance, Distance::Jaccard | Distance::Custom(_)) {
+            return None;
+        }
+
+        let values = (0..self.subvectors)
+            .map(|i| {
+                let sub = &vector.0[self.range(i)];
+                let centroids = self.centroids(i);
+                match distance {
+                    Distance::Euclidean | Distance::SquaredEuclidean => {
+                        centroids.map(|c| squared(sub, c)).collect()
+                    }
+                    Distance::Dot | Distance::Cosine => {
+                        centroids.map(|c| dot(sub, c)).collect()
+                    }
+                    _ => {
+                        let sub = Vector::from(sub.to_vec());
+                        let iter = centroids.map(|c| c.iter().copied());
+                        iter.map(|c| distance.calculate_iter(&sub, c)).collect()
+                    }
+                }
+            })
+            .collect();
+
+        let norm = vector.0.iter().map(|x| x * x).sum::<f32>().sqrt();
+        Some(ProductTable { distance: distance.clone(), values, norm })
+    }
+
+    /// Calculates the distance to the quantized vector
+    /// using the distance table of the query.
+    /// * `table`: Distance table of the query.
+    /// * `code`: Quantized vector.
+    pub fn distance(&self, table: &ProductTable, code: &Code) -> f32 {
+        let iter = code.values.iter().enumerate();
+        let values = iter.map(|(i, c)| table.values[i][*c as usize]);
+        match table.distance {
+            Distance::Chebyshev => values.fold(0.0, f32::max),
+            Distance::Euclidean => values.sum::<f32>().sqrt(),
+            Distance::Cosine => {
+                let iter = code.values.iter().enumerate();
+                let norm: f32 =
+                    iter.map(|(i, c)| self.norms[i][*c as usize]).sum();
+                values.sum::<f32>() / (table.norm * norm.sqrt())
+            }
+            _ => values.sum(),
+        }
+    }
+
+    /// Returns the dimension range of the sub-vector.
+    fn range(&self, index: usize) -> Range<usize> {
+        let start = self.dimension * index / self.subvectors;
+        let end = self.dimension * (index + 1) / self.subvectors;
+        start..end
+    }
+
+    /// Returns the centroids of the sub-vector codebook.
+    fn centroids(&self, index: usize) -> impl Iterator<Item = &[f32]> {
+        let len = self.range(index).len().max(1);
+        self.codebooks[index].chunks(len)
+    }
+}
+
+/// The distances between the sub-vectors of a query and the
+/// centroids of the product quantizer codebooks.
+#[derive(Debug, Clone)]
+pub struct ProductTable {
+    distance: Distance,
+    /// The partial distance or dot product of each centroid.
+    values: Vec<Vec<f32>>,
+    /// The norm of the query for the cosine similarity.
+    norm: f32,
+}
+
+/// The binary quantizer that keeps the sign bit of each value.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct BinaryQuantizer {
+    /// The dimension of the quantized vectors.
+    pub dimension: usize,
+}
+
+impl BinaryQuantizer {
+    /// Sets the dimension from the vectors if it's not set yet.
+    /// * `vectors`: Vectors that will be quantized.
+    pub fn fit<'a>(&mut self, mut vectors: impl Iterator<Item = &'a Vector>) {
+        if self.dimension == 0 {
+            self.dimension = vectors.next().map_or(0, |v| v.len());
+        }
+    }
+
+    /// Packs the sign bits of the vector values into bytes.
+    /// A bit is set if the value is positive.
+    /// * `vector`: Vector to quantize.
+    pub fn encode(&self, vector: &Vector) -> Code {
+        let mut values = vec![0u8; vector.len().div_ceil(8)];
+        for (i, value) in vector.0.iter().enumerate() {
+            if *value > 0.0 {
+                values[i / 8] |= 1 << (i % 8);
+            }
+        }
+
+        Code { values, scale: None }
+    }
+
+    /// Returns 1.0 for the set bits and -1.0 for the others.
+    /// * `code`: Quantized vector.
+    pub fn decode<'a>(
+        &'a self,
+        code: &'a Code,
+    ) -> impl Iterator<Item = f32> + 'a {
+        (0..self.dimension).map(|i| match code.values[i / 8] >> (i % 8) & 1 {
+            1 => 1.0,
+            _ => -1.0,
+        })
+    }
+
+    /// Returns the number of different bits of the quantized vectors.
+    /// * `a`: Packed bits of the first vector.
+    /// * `b`: Packed bits of the second vector.
+    pub fn hamming(a: &[u8], b: &[u8]) -> f32 {
+        let zip = a.iter().zip(b.iter());
+        zip.map(|(x, y)| (x ^ y).count_ones()).sum::<u32>() as f32
+    }
+}
+
+/// The values computed once per query to look up the distances
+/// to the quantized vectors.
+#[derive(Debug, Clone)]
+pub enum DistanceTable {
+    /// Query with the scales of the scalar quantizer folded in.
+    Scalar(ScalarTable),
+    /// Distances to the product quantizer centroids.
+    Product(ProductTable),
+    /// Sign bits of the query for the Hamming distance.
+    Binary(Code),
+}
+
+/// The quantizer of the stored vectors.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum Quantizer {
+    /// Scalar quantizer per dimension or per vector.
+    Scalar(ScalarQuantizer),
+    /// Product quantizer with trained codebooks.
+    Product(ProductQuantizer),
+    /// Binary quantizer of the sign bits.
+    Binary(BinaryQuantizer),
+}
+
+impl Quantizer {
+    /// Creates the quantizer for the collection config.
+    /// * `config`: Collection configuration.
+    pub fn new(config: &Config) -> Option<Self> {
+        match config.quantization? {
+            Quantization::Product => {
+                let quantizer = ProductQuantizer::new(config.subvectors);
+                Some(Quantizer::Product(quantizer))
+            }
+            Quantization::Binary => {
+                Some(Quantizer::Binary(BinaryQuantizer::default()))
+            }
+            kind => Some(Quantizer::Scalar(ScalarQuantizer::new(kind))),
+        }
+    }
+
+    /// Returns true if the vectors can be quantized. The scales of
+    /// the scalar quantizer have to be fitted and the product
+    /// quantizer has to be trained before they can be used.
+    pub fn is_trained(&self) -> bool {
+        match self {
+            Quantizer::Scalar(quantizer) => quantizer.is_fitted(),
+            Quantizer::Product(quantizer) => quantizer.is_trained(),
+            Quantizer::Binary(_) => true,
+        }
+    }
+
+    /// Quantizes the vector.
+    /// * `vector`: Vector to quantize.
+    pub fn encode(&self, vector: &Vector) -> Code {
+        match self {
+            Quantizer::Scalar(quantizer) => quantizer.encode(vector),
+            Quantizer::Product(quantizer) => quantizer.encode(vector),
+            Quantizer::Binary(quantizer) => quantizer.encode(vector),
+        }
+    }
+
+    /// Returns the approximate vector of the quantized vector.
+    /// * `code`: Quantized vector.
+    pub fn decode(&self, code: &Code) -> Vector {
+        match self {
+            Quantizer::Scalar(quantizer) => quantizer.decode(code).collect(),
+            Quantizer::Product(quantizer) => quantizer.decode(code),
+            Quantizer::Binary(quantizer) => quantizer.decode(code).collect(),
+        }
+    }
+
+    /// Returns the distance table of the query if the distances
+    /// to the quantized vectors can be looked up.
+    /// * `distance`: Distance function to use.
+    /// * `vector`: Query vector.
+    pub fn table(
+        &self,
+        distance: &Distance,
+        vector: &Vector,
+    ) -> Option<DistanceTable> {
+        match self {
+            Quantizer::Scalar(q) => {
+                q.table(distance, vector).map(DistanceTable::Scalar)
+            }
+            Quantizer::Product(q) if q.is_trained() => {
+                q.table(distance, vector).map(DistanceTable::Product)
+            }
+            Quantizer::Binary(q) if *distance == Distance::Hamming => {
+                Some(DistanceTable::Binary(q.encode(vector)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Calculates the distance between the vector and the quantized
+    /// vector. The distance table is used if it's given.
+    /// * `distance`: Distance function to use.
+    /// * `vector`: Full precision vector.
+    /// * `table`: Distance table of the vector.
+    /// * `code`: Quantized vector.
+    pub fn distance(
+        &self,
+        distance: &Distance,
+        vector: &Vector,
+        table: Option<&DistanceTable>,
+        code: &Code,
+    ) -> f32 {
+        match (self, table) {
+            (Quantizer::Scalar(q), Some(DistanceTable::Scalar(table))) => {
+                q.distance(table, code)
+            }
+            (Quantizer::Scalar(quantizer), _) => {
+                distance.calculate_iter(vector, quantizer.decode(code))
+            }
+            (Quantizer::Product(q), Some(DistanceTable::Product(table))) => {
+                q.distance(table, code)
+            }
+            (Quantizer::Product(quantizer), _) => {
+                distance.calculate(vector, &quantizer.decode(code))
+            }
+            (Quantizer::Binary(_), Some(DistanceTable::Binary(query))) => {
+                BinaryQuantizer::hamming(&query.values, &code.values)
+            }
+            (Quantizer::Binary(quantizer), _) => {
+                distance.calculate_iter(vector, quantizer.decode(code))
+            }
+        }
+    }
+}
+
+/// Clusters the points with k-means and returns the concatenated
+/// centroids. The centroids are initialized with random points.
+/// * `points`: Points to cluster.
+/// * `k`: Number of clusters.
+/// * `len`: Length of the points.
+fn kmeans(points: &[&[f32]], k: usize, len: usize) -> Vec<f32> {
+    let mut rng = rand::thread_rng();
+    let mut centroids: Vec<f32> = sample(&mut rng, points.len(), k)
+        .into_iter()
+        .flat_map(|i| points[i].iter().copied())
+        .collect();
+
+    if len == 0 {
+        return centroids;
+    }
+
+    for _ in 0..ITERATIONS {
+        let assignments: Vec<usize> = points
+            .par_iter()
+            .map(|point| {
+                let centroids = centroids.chunks(len);
+                let distances = centroids.map(|c| squared(point, c));
+                let iter = distances.enumerate();
+                iter.min_by(|a, b| a.1.total_cmp(&b.1)).unwrap().0
+            })
+            .collect();
+
+        let mut sums = vec![0.0; k * len];
+        let mut counts = vec![0; k];
+        for (point, cluster) in points.iter().zip(assignments) {
+            counts[cluster] += 1;
+            let sum = &mut sums[cluster * len..(cluster + 1) * len];
+            for (s, x) in sum.iter_mut().zip(point.iter()) {
+                *s += x;
+            }
+        }
+
+        // Clusters without points keep their centroids.
+        for (cluster, count) in counts.into_iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+
+            let range = cluster * len..(cluster + 1) * len;
+            for (c, s) in centroids[range.clone()].iter_mut().zip(&sums[range])
+            {
+                *c = s / count as f32;
+            }
+        }
+    }
+
+    centroids
+}
+
+fn squared(a: &[f32], b: &[f32]) -> f32 {
+    Kernel::detect().squared(a, b)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    Kernel::detect().dot(a, b)
+}
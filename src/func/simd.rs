@@ -0,0 +1,359 @@
+use std::sync::OnceLock;
+
+// The kernels are selected at runtime based on the CPU features
+// so the same binary runs on any CPU of the target architecture.
+// Each kernel processes the vectors in chunks of its register
+// width and handles the remaining values with the scalar kernel.
+// The AVX-512 kernels are behind the avx512 feature because
+// their intrinsics need a newer Rust than the rest of the crate.
+
+/// The distance kernels implemented with an instruction set.
+#[derive(Debug)]
+pub struct Kernel {
+    name: &'static str,
+    dot: fn(&[f32], &[f32]) -> f32,
+    squared: fn(&[f32], &[f32]) -> f32,
+    cosine: fn(&[f32], &[f32]) -> [f32; 3],
+}
+
+/// The portable kernel used when no SIMD instruction set is available.
+static SCALAR: Kernel =
+    Kernel { name: "scalar", dot, squared, cosine: dot_and_norms };
+
+impl Kernel {
+    /// Returns the fastest kernel supported by the CPU.
+    /// The CPU features are only detected on the first call.
+    pub fn detect() -> &'static Kernel {
+        static KERNEL: OnceLock<&'static Kernel> = OnceLock::new();
+        KERNEL.get_or_init(|| *Self::available().last().unwrap())
+    }
+
+    /// Returns the kernels supported by the CPU ordered from the
+    /// scalar kernel to the fastest one.
+    pub fn available() -> Vec<&'static Kernel> {
+        #[allow(unused_mut)]
+        let mut kernels = vec![&SCALAR];
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            kernels.push(&x86::SSE);
+            if is_x86_feature_detected!("avx2")
+                && is_x86_feature_detected!("fma")
+            {
+                kernels.push(&x86::AVX2);
+            }
+
+            #[cfg(feature = "avx512")]
+            if is_x86_feature_detected!("avx512f") {
+                kernels.push(&x86::AVX512);
+            }
+        }
+
+        // NEON is part of the baseline of the AArch64 targets.
+        #[cfg(target_arch = "aarch64")]
+        kernels.push(&arm::NEON);
+
+        kernels
+    }
+
+    /// Returns the name of the instruction set of the kernel.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Calculates the dot product of the values.
+    pub fn dot(&self, a: &[f32], b: &[f32]) -> f32 {
+        (self.dot)(a, b)
+    }
+
+    /// Calculates the squared Euclidean distance of the values.
+    pub fn squared(&self, a: &[f32], b: &[f32]) -> f32 {
+        (self.squared)(a, b)
+    }
+
+    /// Calculates the cosine similarity of the values. The dot
+    /// product and both norms are accumulated in a single pass.
+    pub fn cosine(&self, a: &[f32], b: &[f32]) -> f32 {
+        let [dot, ma, mb] = (self.cosine)(a, b);
+        dot / (ma.sqrt() * mb.sqrt())
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn squared(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// Returns the dot product and the squared norms of the values.
+fn dot_and_norms(a: &[f32], b: &[f32]) -> [f32; 3] {
+    let mut sums = [0.0; 3];
+    for (x, y) in a.iter().zip(b.iter()) {
+        sums[0] += x * y;
+        sums[1] += x * x;
+        sums[2] += y * y;
+    }
+
+    sums
+}
+
+/// Returns the length of the values processed in SIMD chunks.
+fn chunked(a: &[f32], b: &[f32], width: usize) -> usize {
+    a.len().min(b.len()) / width * width
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::*;
+    use std::arch::x86_64::*;
+
+    // The kernels are only reachable through Kernel::available
+    // which checks that the CPU supports their target features.
+
+    pub static SSE: Kernel = Kernel {
+        name: "sse",
+        dot: |a, b| unsafe { sse_dot(a, b) },
+        squared: |a, b| unsafe { sse_squared(a, b) },
+        cosine: |a, b| unsafe { sse_cosine(a, b) },
+    };
+
+    pub static AVX2: Kernel = Kernel {
+        name: "avx2",
+        dot: |a, b| unsafe { avx2_dot(a, b) },
+        squared: |a, b| unsafe { avx2_squared(a, b) },
+        cosine: |a, b| unsafe { avx2_cosine(a, b) },
+    };
+
+    #[cfg(feature = "avx512")]
+    pub static AVX512: Kernel = Kernel {
+        name: "avx512",
+        dot: |a, b| unsafe { avx512_dot(a, b) },
+        squared: |a, b| unsafe { avx512_squared(a, b) },
+        cosine: |a, b| unsafe { avx512_cosine(a, b) },
+    };
+
+    #[inline]
+    #[target_feature(enable = "sse")]
+    unsafe fn sse_sum(v: __m128) -> f32 {
+        let mut values = [0.0; 4];
+        _mm_storeu_ps(values.as_mut_ptr(), v);
+        values.iter().sum()
+    }
+
+    #[target_feature(enable = "sse")]
+    unsafe fn sse_dot(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 4);
+        let mut sum = _mm_setzero_ps();
+        for i in (0..n).step_by(4) {
+            let x = _mm_loadu_ps(a.as_ptr().add(i));
+            let y = _mm_loadu_ps(b.as_ptr().add(i));
+            sum = _mm_add_ps(sum, _mm_mul_ps(x, y));
+        }
+
+        sse_sum(sum) + dot(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "sse")]
+    unsafe fn sse_squared(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 4);
+        let mut sum = _mm_setzero_ps();
+        for i in (0..n).step_by(4) {
+            let x = _mm_loadu_ps(a.as_ptr().add(i));
+            let y = _mm_loadu_ps(b.as_ptr().add(i));
+            let diff = _mm_sub_ps(x, y);
+            sum = _mm_add_ps(sum, _mm_mul_ps(diff, diff));
+        }
+
+        sse_sum(sum) + squared(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "sse")]
+    unsafe fn sse_cosine(a: &[f32], b: &[f32]) -> [f32; 3] {
+        let n = chunked(a, b, 4);
+        let mut dots = _mm_setzero_ps();
+        let mut norms_a = _mm_setzero_ps();
+        let mut norms_b = _mm_setzero_ps();
+        for i in (0..n).step_by(4) {
+            let x = _mm_loadu_ps(a.as_ptr().add(i));
+            let y = _mm_loadu_ps(b.as_ptr().add(i));
+            dots = _mm_add_ps(dots, _mm_mul_ps(x, y));
+            norms_a = _mm_add_ps(norms_a, _mm_mul_ps(x, x));
+            norms_b = _mm_add_ps(norms_b, _mm_mul_ps(y, y));
+        }
+
+        let [dot, ma, mb] = dot_and_norms(&a[n..], &b[n..]);
+        [sse_sum(dots) + dot, sse_sum(norms_a) + ma, sse_sum(norms_b) + mb]
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn avx2_sum(v: __m256) -> f32 {
+        let mut values = [0.0; 8];
+        _mm256_storeu_ps(values.as_mut_ptr(), v);
+        values.iter().sum()
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn avx2_dot(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 8);
+        let mut sum = _mm256_setzero_ps();
+        for i in (0..n).step_by(8) {
+            let x = _mm256_loadu_ps(a.as_ptr().add(i));
+            let y = _mm256_loadu_ps(b.as_ptr().add(i));
+            sum = _mm256_fmadd_ps(x, y, sum);
+        }
+
+        avx2_sum(sum) + dot(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn avx2_squared(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 8);
+        let mut sum = _mm256_setzero_ps();
+        for i in (0..n).step_by(8) {
+            let x = _mm256_loadu_ps(a.as_ptr().add(i));
+            let y = _mm256_loadu_ps(b.as_ptr().add(i));
+            let diff = _mm256_sub_ps(x, y);
+            sum = _mm256_fmadd_ps(diff, diff, sum);
+        }
+
+        avx2_sum(sum) + squared(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn avx2_cosine(a: &[f32], b: &[f32]) -> [f32; 3] {
+        let n = chunked(a, b, 8);
+        let mut dots = _mm256_setzero_ps();
+        let mut norms_a = _mm256_setzero_ps();
+        let mut norms_b = _mm256_setzero_ps();
+        for i in (0..n).step_by(8) {
+            let x = _mm256_loadu_ps(a.as_ptr().add(i));
+            let y = _mm256_loadu_ps(b.as_ptr().add(i));
+            dots = _mm256_fmadd_ps(x, y, dots);
+            norms_a = _mm256_fmadd_ps(x, x, norms_a);
+            norms_b = _mm256_fmadd_ps(y, y, norms_b);
+        }
+
+        let [dot, ma, mb] = dot_and_norms(&a[n..], &b[n..]);
+        [avx2_sum(dots) + dot, avx2_sum(norms_a) + ma, avx2_sum(norms_b) + mb]
+    }
+
+    #[cfg(feature = "avx512")]
+    #[clippy::msrv = "1.89"]
+    #[target_feature(enable = "avx512f")]
+    unsafe fn avx512_dot(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 16);
+        let mut sum = _mm512_setzero_ps();
+        for i in (0..n).step_by(16) {
+            let x = _mm512_loadu_ps(a.as_ptr().add(i));
+            let y = _mm512_loadu_ps(b.as_ptr().add(i));
+            sum = _mm512_fmadd_ps(x, y, sum);
+        }
+
+        _mm512_reduce_add_ps(sum) + dot(&a[n..], &b[n..])
+    }
+
+    #[cfg(feature = "avx512")]
+    #[clippy::msrv = "1.89"]
+    #[target_feature(enable = "avx512f")]
+    unsafe fn avx512_squared(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 16);
+        let mut sum = _mm512_setzero_ps();
+        for i in (0..n).step_by(16) {
+            let x = _mm512_loadu_ps(a.as_ptr().add(i));
+            let y = _mm512_loadu_ps(b.as_ptr().add(i));
+            let diff = _mm512_sub_ps(x, y);
+            sum = _mm512_fmadd_ps(diff, diff, sum);
+        }
+
+        _mm512_reduce_add_ps(sum) + squared(&a[n..], &b[n..])
+    }
+
+    #[cfg(feature = "avx512")]
+    #[clippy::msrv = "1.89"]
+    #[target_feature(enable = "avx512f")]
+    unsafe fn avx512_cosine(a: &[f32], b: &[f32]) -> [f32; 3] {
+        let n = chunked(a, b, 16);
+        let mut dots = _mm512_setzero_ps();
+        let mut norms_a = _mm512_setzero_ps();
+        let mut norms_b = _mm512_setzero_ps();
+        for i in (0..n).step_by(16) {
+            let x = _mm512_loadu_ps(a.as_ptr().add(i));
+            let y = _mm512_loadu_ps(b.as_ptr().add(i));
+            dots = _mm512_fmadd_ps(x, y, dots);
+            norms_a = _mm512_fmadd_ps(x, x, norms_a);
+            norms_b = _mm512_fmadd_ps(y, y, norms_b);
+        }
+
+        let [dot, ma, mb] = dot_and_norms(&a[n..], &b[n..]);
+        [
+            _mm512_reduce_add_ps(dots) + dot,
+            _mm512_reduce_add_ps(norms_a) + ma,
+            _mm512_reduce_add_ps(norms_b) + mb,
+        ]
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod arm {
+    use super::*;
+    use std::arch::aarch64::*;
+
+    pub static NEON: Kernel = Kernel {
+        name: "neon",
+        dot: |a, b| unsafe { neon_dot(a, b) },
+        squared: |a, b| unsafe { neon_squared(a, b) },
+        cosine: |a, b| unsafe { neon_cosine(a, b) },
+    };
+
+    #[target_feature(enable = "neon")]
+    unsafe fn neon_dot(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 4);
+        let mut sum = vdupq_n_f32(0.0);
+        for i in (0..n).step_by(4) {
+            let x = vld1q_f32(a.as_ptr().add(i));
+            let y = vld1q_f32(b.as_ptr().add(i));
+            sum = vfmaq_f32(sum, x, y);
+        }
+
+        vaddvq_f32(sum) + dot(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn neon_squared(a: &[f32], b: &[f32]) -> f32 {
+        let n = chunked(a, b, 4);
+        let mut sum = vdupq_n_f32(0.0);
+        for i in (0..n).step_by(4) {
+            let x = vld1q_f32(a.as_ptr().add(i));
+            let y = vld1q_f32(b.as_ptr().add(i));
+            let diff = vsubq_f32(x, y);
+            sum = vfmaq_f32(sum, diff, diff);
+        }
+
+        vaddvq_f32(sum) + squared(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn neon_cosine(a: &[f32], b: &[f32]) -> [f32; 3] {
+        let n = chunked(a, b, 4);
+        let mut dots = vdupq_n_f32(0.0);
+        let mut norms_a = vdupq_n_f32(0.0);
+        let mut norms_b = vdupq_n_f32(0.0);
+        for i in (0..n).step_by(4) {
+            let x = vld1q_f32(a.as_ptr().add(i));
+            let y = vld1q_f32(b.as_ptr().add(i));
+            dots = vfmaq_f32(dots, x, y);
+            norms_a = vfmaq_f32(norms_a, x, x);
+            norms_b = vfmaq_f32(norms_b, y, y);
+        }
+
+        let [dot, ma, mb] = dot_and_norms(&a[n..], &b[n..]);
+        [
+            vaddvq_f32(dots) + dot,
+            vaddvq_f32(norms_a) + ma,
+            vaddvq_f32(norms_b) + mb,
+        ]
+    }
+}
@@ -0,0 +1,239 @@
+use super::*;
+
+/// The sparse vector of non-zero values by their dimension index
+/// like SPLADE or BM25 term weights.
+#[pyclass(module = "sahomedb.vector")]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(PartialEq, PartialOrd)]
+pub struct SparseVector {
+    indices: Vec<u32>,
+    values: Vec<f32>,
+}
+
+// Methods available to Python.
+// If this implementation is modified, make sure to modify:
+// - py/tests/test_vector.py
+// - py/sahomedb/vector.pyi
+#[pymethods]
+impl SparseVector {
+    /// Creates a sparse vector from the indices and their values.
+    /// The values are sorted by their indices.
+    /// * `indices`: Unique dimension indices of the values.
+    /// * `values`: Values of the dimensions.
+    #[new]
+    pub fn new(indices: Vec<u32>, values: Vec<f32>) -> Result<Self, Error> {
+        if indices.len() != values.len() {
+            let message = "The sparse vector indices and values must \
+                have the same length.";
+            return Err(message.into());
+        }
+
+        let mut pairs: Vec<(u32, f32)> =
+            indices.into_iter().zip(values).collect();
+        pairs.sort_by_key(|(index, _)| *index);
+
+        if pairs.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err("The sparse vector indices must be unique.".into());
+        }
+
+        let (indices, values) = pairs.into_iter().unzip();
+        Ok(Self { indices, values })
+    }
+
+    /// Returns the dimension indices of the values.
+    #[getter]
+    pub fn indices(&self) -> Vec<u32> {
+        self.indices.clone()
+    }
+
+    /// Returns the values sorted by their indices.
+    #[getter]
+    pub fn values(&self) -> Vec<f32> {
+        self.values.clone()
+    }
+
+    /// Returns the number of non-zero values.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns true if the vector has no values.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Calculates the dot product with the other sparse vector.
+    /// * `other`: Sparse vector to multiply with.
+    pub fn dot(&self, other: &SparseVector) -> f32 {
+        let (mut i, mut j, mut sum) = (0, 0, 0.0);
+        while i < self.len() && j < other.len() {
+            match self.indices[i].cmp(&other.indices[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    sum += self.values[i] * other.values[j];
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+
+        sum
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+}
+
+impl SparseVector {
+    /// Returns an iterator of the indices and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.indices.iter().copied().zip(self.values.iter().copied())
+    }
+}
+
+/// The method to combine the dense and sparse search results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fusion {
+    /// Weighted sum of the dense and sparse scores. The dense score
+    /// is the similarity for the dot and cosine functions and the
+    /// negated distance for the others. The scores should be on a
+    /// similar scale like cosine similarity and normalized weights.
+    /// * `alpha`: Weight of the dense score from 0 to 1. The sparse
+    ///   score is weighted by `1 - alpha`.
+    WeightedSum(f32),
+    /// Reciprocal rank fusion which sums `1 / (k + rank)` of each
+    /// search the record is found by. It only uses the ranks so the
+    /// scales of the scores don't matter.
+    /// * `k`: Rank constant, usually 60.
+    ReciprocalRank(f32),
+}
+
+impl Fusion {
+    /// Creates a new fusion method from a string.
+    /// Available options:
+    /// * `weighted`: Weighted sum with the dense weight `alpha`.
+    /// * `rrf`: Reciprocal rank fusion with the rank constant `k`.
+    pub fn from(fusion: &str, alpha: f32, k: f32) -> Result<Self, Error> {
+        match fusion {
+            "weighted" => Ok(Fusion::WeightedSum(alpha)),
+            "rrf" => Ok(Fusion::ReciprocalRank(k)),
+            _ => Err("Fusion method not supported.".into()),
+        }
+    }
+
+    /// Combines the rankings of the dense and sparse searches into
+    /// the fused scores of the records sorted from the highest.
+    /// * `dense`: Records found by the dense search, best first.
+    /// * `sparse`: Records found by the sparse search, best first.
+    /// * `dense_score`: Returns the dense score of a record.
+    /// * `sparse_score`: Returns the sparse score of a record.
+    pub(crate) fn fuse(
+        &self,
+        dense: &[VectorID],
+        sparse: &[VectorID],
+        dense_score: impl Fn(&VectorID) -> f32,
+        sparse_score: impl Fn(&VectorID) -> f32,
+    ) -> Vec<(VectorID, f32)> {
+        let mut scores: HashMap<VectorID, f32> = HashMap::new();
+        match self {
+            // Both scores are calculated for the records found by
+            // either search so a missing score doesn't count as 0.
+            Fusion::WeightedSum(alpha) => {
+                for id in dense.iter().chain(sparse) {
+                    scores.entry(*id).or_insert_with(|| {
+                        alpha * dense_score(id)
+                            + (1.0 - alpha) * sparse_score(id)
+                    });
+                }
+            }
+            Fusion::ReciprocalRank(k) => {
+                for ranking in [dense, sparse] {
+                    for (rank, id) in ranking.iter().enumerate() {
+                        let score = 1.0 / (k + rank as f32 + 1.0);
+                        *scores.entry(*id).or_default() += score;
+                    }
+                }
+            }
+        }
+
+        let mut scores: Vec<(VectorID, f32)> = scores.into_iter().collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores
+    }
+}
+
+/// The sparse vectors of the records with an inverted index
+/// from each dimension to the records with a value in it.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct SparseIndex {
+    vectors: HashMap<VectorID, SparseVector>,
+    postings: HashMap<u32, HashMap<VectorID, f32>>,
+}
+
+impl SparseIndex {
+    /// Assigns the sparse vector to the record or removes
+    /// its sparse vector if None.
+    pub fn set(&mut self, id: &VectorID, vector: Option<&SparseVector>) {
+        if let Some(old) = self.vectors.remove(id) {
+            for (index, _) in old.iter() {
+                let posting = self.postings.get_mut(&index).unwrap();
+                posting.remove(id);
+                if posting.is_empty() {
+                    self.postings.remove(&index);
+                }
+            }
+        }
+
+        if let Some(vector) = vector {
+            for (index, value) in vector.iter() {
+                self.postings.entry(index).or_default().insert(*id, value);
+            }
+
+            self.vectors.insert(*id, vector.clone());
+        }
+    }
+
+    /// Returns the sparse vector of the record if it has one.
+    pub fn get(&self, id: &VectorID) -> Option<&SparseVector> {
+        self.vectors.get(id)
+    }
+
+    /// Returns the dot product of the query and the record.
+    /// Records without a sparse vector score 0.
+    pub fn score(&self, query: &SparseVector, id: &VectorID) -> f32 {
+        self.get(id).map_or(0.0, |vector| query.dot(vector))
+    }
+
+    /// Returns the records sharing a dimension with the query
+    /// sorted by their dot product from the highest.
+    /// * `query`: Sparse vector to search.
+    /// * `n`: Number of records to return.
+    pub fn search(
+        &self,
+        query: &SparseVector,
+        n: usize,
+    ) -> Vec<(VectorID, f32)> {
+        let mut scores: HashMap<VectorID, f32> = HashMap::new();
+        for (index, weight) in query.iter() {
+            let posting = match self.postings.get(&index) {
+                Some(posting) => posting,
+                None => continue,
+            };
+
+            for (id, value) in posting {
+                *scores.entry(*id).or_default() += weight * value;
+            }
+        }
+
+        let mut scores: Vec<(VectorID, f32)> = scores.into_iter().collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        scores.truncate(n);
+        scores
+    }
+}
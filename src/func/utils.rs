@@ -4,12 +4,20 @@ use super::*;
 
 pub const INVALID: VectorID = VectorID(u32::MAX);
 
-/// The M value for the HNSW algorithm.
-pub const M: usize = 32;
+/// The ratio of the collection size under which filtered searches
+/// scan the indexed candidates instead of traversing the graph.
+pub const BRUTE_FORCE_RATIO: f32 = 0.05;
+
+/// The ratio of the collection size a filtered search visits in the
+/// base layer before it scans the allowed records instead.
+pub const FILTER_VISIT_RATIO: f32 = 0.1;
 
 pub trait Layer {
     type Slice: Deref<Target = [VectorID]>;
     fn nearest_iter(&self, vector_id: &VectorID) -> NearestIter<Self::Slice>;
+
+    /// Returns true if the vector ID has a node in the layer.
+    fn has_node(&self, vector_id: &VectorID) -> bool;
 }
 
 pub struct NearestIter<T> {
@@ -74,20 +82,27 @@ impl LayerID {
     pub fn is_zero(&self) -> bool {
         self.0 == 0
     }
-}
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
-pub struct BaseNode(#[serde(with = "BigArray")] pub [VectorID; M * 2]);
+    /// Returns a random layer for a new node. Each layer is `ml`
+    /// times as likely as the layer below like in the built index.
+    /// * `ml`: Layer multiplier of the index.
+    /// * `max`: Highest layer to return.
+    pub fn random(ml: f32, max: usize) -> Self {
+        let mut layer = 0;
+        while layer < max && random::<f32>() < ml {
+            layer += 1;
+        }
 
-impl Default for BaseNode {
-    fn default() -> Self {
-        Self([INVALID; M * 2])
+        Self(layer)
     }
 }
 
-impl BaseNode {
-    pub fn allocate(&mut self, mut iter: impl Iterator<Item = VectorID>) {
-        for slot in self.0.iter_mut() {
+/// The neighbors of a node in a graph layer sorted by distance.
+/// The unused slots at the end are invalid vector IDs.
+pub trait Node: Deref<Target = [VectorID]> + AsMut<[VectorID]> {
+    /// Replaces the neighbors with the vector IDs.
+    fn allocate(&mut self, mut iter: impl Iterator<Item = VectorID>) {
+        for slot in self.as_mut().iter_mut() {
             if let Some(vector_id) = iter.next() {
                 *slot = vector_id;
             } else if *slot != INVALID {
@@ -98,49 +113,48 @@ impl BaseNode {
         }
     }
 
-    /// Inserts a vector ID to the base node at the index.
-    pub fn insert(&mut self, index: usize, vector_id: &VectorID) {
-        if index >= self.0.len() {
+    /// Inserts a vector ID to the node at the index.
+    fn insert(&mut self, index: usize, vector_id: &VectorID) {
+        let node = self.as_mut();
+        if index >= node.len() {
             return;
         }
 
         // Shift the vector IDs.
-        if self.0[index].is_valid() {
-            let end = M * 2 - 1;
-            self.0.copy_within(index..end, index + 1);
+        if node[index].is_valid() {
+            let end = node.len() - 1;
+            node.copy_within(index..end, index + 1);
         }
 
         self.set(index, vector_id)
     }
 
     /// Sets the vector ID at the index.
-    pub fn set(&mut self, index: usize, vector_id: &VectorID) {
-        self.0[index] = *vector_id;
+    fn set(&mut self, index: usize, vector_id: &VectorID) {
+        self.as_mut()[index] = *vector_id;
     }
 }
 
-impl Index<&VectorID> for [RwLock<BaseNode>] {
-    type Output = RwLock<BaseNode>;
+impl<N: Node> Index<&VectorID> for [RwLock<N>] {
+    type Output = RwLock<N>;
     fn index(&self, index: &VectorID) -> &Self::Output {
         &self[index.0 as usize]
     }
 }
 
-impl Deref for BaseNode {
-    type Target = [VectorID];
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl<'a> Layer for &'a [BaseNode] {
+impl<'a, N: Node> Layer for &'a [N] {
     type Slice = &'a [VectorID];
     fn nearest_iter(&self, vector_id: &VectorID) -> NearestIter<Self::Slice> {
         NearestIter::new(&self[vector_id.0 as usize])
     }
+
+    fn has_node(&self, vector_id: &VectorID) -> bool {
+        let node = self.get(vector_id.0 as usize);
+        node.is_some_and(|node| !node.is_empty())
+    }
 }
 
-impl<'a> Layer for &'a [RwLock<BaseNode>] {
+impl<'a, N: Node> Layer for &'a [RwLock<N>] {
     type Slice = MappedRwLockReadGuard<'a, [VectorID]>;
     fn nearest_iter(&self, vector_id: &VectorID) -> NearestIter<Self::Slice> {
         NearestIter::new(RwLockReadGuard::map(
@@ -148,30 +162,180 @@ impl<'a> Layer for &'a [RwLock<BaseNode>] {
             Deref::deref,
         ))
     }
+
+    fn has_node(&self, vector_id: &VectorID) -> bool {
+        let node = self.get(vector_id.0 as usize);
+        node.is_some_and(|node| !node.read().is_empty())
+    }
+}
+
+/// A graph layer whose nodes are changed one at a time.
+pub trait LayerMut {
+    type Node: Node;
+
+    /// Calls the function with the node of the vector ID
+    /// and returns its result if the layer has the node.
+    fn update<T>(
+        &mut self,
+        vector_id: &VectorID,
+        f: impl FnOnce(&mut Self::Node) -> T,
+    ) -> Option<T>;
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
-pub struct UpperNode(#[serde(with = "BigArray")] pub [VectorID; M]);
+impl<N: Node> LayerMut for [N] {
+    type Node = N;
+    fn update<T>(
+        &mut self,
+        vector_id: &VectorID,
+        f: impl FnOnce(&mut N) -> T,
+    ) -> Option<T> {
+        self.get_mut(vector_id.0 as usize).map(f)
+    }
+}
+
+impl<N: Node> LayerMut for &[RwLock<N>] {
+    type Node = N;
+    fn update<T>(
+        &mut self,
+        vector_id: &VectorID,
+        f: impl FnOnce(&mut N) -> T,
+    ) -> Option<T> {
+        let node = self.get(vector_id.0 as usize)?;
+        Some(f(&mut node.write()))
+    }
+}
+
+/// The neighbors of a node in the base layer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BaseNode(pub Box<[VectorID]>);
+
+impl BaseNode {
+    /// Creates a node without neighbors.
+    /// * `m0`: Max number of neighbors in the base layer.
+    pub fn new(m0: usize) -> Self {
+        Self(vec![INVALID; m0].into())
+    }
+}
+
+impl Node for BaseNode {}
+
+impl AsMut<[VectorID]> for BaseNode {
+    fn as_mut(&mut self) -> &mut [VectorID] {
+        &mut self.0
+    }
+}
+
+impl Deref for BaseNode {
+    type Target = [VectorID];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// The neighbors of a node in an upper layer. The nodes of the
+/// records that are not in the layer have no slots which is
+/// also the default.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct UpperNode(pub Box<[VectorID]>);
 
 impl UpperNode {
-    pub fn from_zero(node: &BaseNode) -> Self {
-        let mut nearest = [INVALID; M];
-        nearest.copy_from_slice(&node.0[..M]);
-        Self(nearest)
+    /// Creates a node in the layer without neighbors.
+    /// * `m`: Max number of neighbors in the upper layers.
+    pub fn new(m: usize) -> Self {
+        Self(vec![INVALID; m].into())
     }
+}
+
+impl Node for UpperNode {}
 
-    pub fn set(&mut self, index: usize, vector_id: &VectorID) {
-        self.0[index] = *vector_id;
+impl AsMut<[VectorID]> for UpperNode {
+    fn as_mut(&mut self) -> &mut [VectorID] {
+        &mut self.0
     }
 }
 
-impl<'a> Layer for &'a [UpperNode] {
-    type Slice = &'a [VectorID];
-    fn nearest_iter(&self, vector_id: &VectorID) -> NearestIter<Self::Slice> {
-        NearestIter::new(&self[vector_id.0 as usize].0)
+impl Deref for UpperNode {
+    type Target = [VectorID];
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Adds the node to the upper layers up to its level and removes
+/// it from the layers above. The layers are added or extended to
+/// fit the node if needed. Level 0 removes it from all of them.
+/// * `layers`: Upper layers of the graph from the lowest.
+/// * `vector_id`: Vector ID of the node.
+/// * `level`: Highest layer of the node.
+/// * `m`: Max number of neighbors in the upper layers.
+pub fn set_level<N: Default + From<UpperNode>>(
+    layers: &mut Vec<Vec<N>>,
+    vector_id: &VectorID,
+    level: &LayerID,
+    m: usize,
+) {
+    let index = vector_id.0 as usize;
+    if layers.len() < level.0 {
+        layers.resize_with(level.0, Vec::new);
+    }
+
+    for (i, layer) in layers.iter_mut().enumerate() {
+        if i < level.0 {
+            if layer.len() <= index {
+                layer.resize_with(index + 1, N::default);
+            }
+
+            layer[index] = UpperNode::new(m).into();
+        } else if let Some(node) = layer.get_mut(index) {
+            *node = N::default();
+        }
     }
 }
 
+/// Returns the highest layer the node is in or the base layer
+/// if it's not in any upper layer.
+/// * `layers`: Upper layers of the graph from the lowest.
+/// * `vector_id`: Vector ID of the node.
+pub fn level_of<'a, N>(layers: &'a [Vec<N>], vector_id: &VectorID) -> LayerID
+where
+    &'a [N]: Layer,
+{
+    let mut iter = layers.iter().map(|layer| layer.as_slice());
+    let index = iter.rposition(|layer| layer.has_node(vector_id));
+    LayerID(index.map_or(0, |i| i + 1))
+}
+
+/// Returns the node to start the graph traversal from with its
+/// level. It's the first live node in the highest upper layer
+/// with one or the first live node if no upper layer has one.
+/// * `layers`: Upper layers of the graph from the lowest.
+/// * `slots`: Slots of the vector IDs which are invalid if deleted.
+/// * `filter`: Returns false for the nodes that can't be the entry.
+pub fn find_entry<'a, N, F: Fn(&VectorID) -> bool>(
+    layers: &'a [Vec<N>],
+    slots: &[VectorID],
+    filter: &F,
+) -> Option<(VectorID, LayerID)>
+where
+    &'a [N]: Layer,
+{
+    let is_live = |id: &VectorID| {
+        let slot = slots.get(id.0 as usize);
+        slot.is_some_and(|slot| slot.is_valid()) && filter(id)
+    };
+
+    for (i, layer) in layers.iter().enumerate().rev() {
+        let layer = layer.as_slice();
+        let mut ids = (0..layer.len()).map(VectorID::from);
+        if let Some(id) = ids.find(|id| layer.has_node(id) && is_live(id)) {
+            return Some((id, LayerID(i + 1)));
+        }
+    }
+
+    let id = slots.iter().find(|id| is_live(id))?;
+    Some((*id, LayerID(0)))
+}
+
 #[derive(Clone)]
 pub struct Visited {
     store: Vec<u8>,
@@ -184,12 +348,6 @@ impl Visited {
         Self { store: vec![0; capacity], generation: 1 }
     }
 
-    pub fn resize_capacity(&mut self, capacity: usize) {
-        if self.store.len() != capacity {
-            self.store.resize(capacity, self.generation - 1);
-        }
-    }
-
     /// Inserts a vector ID into the visited object.
     pub fn insert(&mut self, vector_id: &VectorID) -> bool {
         let slot = match self.store.get_mut(vector_id.0 as usize) {
@@ -218,8 +376,9 @@ impl Visited {
             return;
         }
 
+        let len = self.store.len();
         self.store.clear();
-        self.store.resize(self.store.len(), 0);
+        self.store.resize(len, 0);
         self.generation = 1;
     }
 }
@@ -235,18 +394,44 @@ pub struct Candidate {
 #[derive(Clone)]
 pub struct Search {
     pub ef: usize,
+    /// Nodes to consider in the upper layers.
+    pub ef_upper: usize,
+    /// Max number of nodes to visit in a layer while the nearest
+    /// neighbors list is not full yet.
+    pub visit_limit: usize,
+    /// True if the last layer search stopped at the visit limit.
+    pub limited: bool,
     pub visited: Visited,
+    visits: usize,
     candidates: BinaryHeap<Reverse<Candidate>>,
     nearest: Vec<Candidate>,
     working: Vec<Candidate>,
     discarded: Vec<Candidate>,
     distance: Distance,
+    /// Distance table of the query for product quantization.
+    table: Option<DistanceTable>,
 }
 
 impl Search {
-    pub fn new(capacity: usize) -> Self {
-        let visited = Visited::with_capacity(capacity);
-        Self { visited, ..Default::default() }
+    /// Creates a search that compares the vectors with the distance
+    /// function of the collection.
+    /// * `capacity`: Number of vector IDs to track as visited.
+    /// * `distance`: Distance function of the collection.
+    pub fn new(capacity: usize, distance: Distance) -> Self {
+        Self {
+            visited: Visited::with_capacity(capacity),
+            candidates: BinaryHeap::new(),
+            nearest: Vec::new(),
+            working: Vec::new(),
+            discarded: Vec::new(),
+            ef: 5,
+            ef_upper: 5,
+            visit_limit: usize::MAX,
+            limited: false,
+            visits: 0,
+            distance,
+            table: None,
+        }
     }
 
     /// Searches the nearest neighbors in the graph layer.
@@ -254,20 +439,46 @@ impl Search {
         &mut self,
         layer: L,
         vector: &Vector,
-        vectors: &HashMap<VectorID, Vector>,
-        links: usize,
+        vectors: &VectorStore,
     ) {
+        self.search_filtered(layer, vector, vectors, &|_| true);
+    }
+
+    /// Searches the nearest neighbors in the graph layer that
+    /// are allowed by the filter. Filtered out nodes are still
+    /// traversed to reach the allowed nodes behind them.
+    pub fn search_filtered<L: Layer, F: Fn(&VectorID) -> bool>(
+        &mut self,
+        layer: L,
+        vector: &Vector,
+        vectors: &VectorStore,
+        filter: &F,
+    ) {
+        self.visits = 0;
+        self.limited = false;
+
         while let Some(Reverse(candidate)) = self.candidates.pop() {
-            // Skip candidates that are too far.
+            // Skip candidates that are too far once the nearest
+            // neighbors list is full. Without a filter, every pushed
+            // candidate is in the list until it's full so none of them
+            // can be further than the list before that.
+            let is_full = self.nearest.len() >= self.ef;
             if let Some(furthest) = self.nearest.last() {
-                if candidate.distance > furthest.distance {
+                if is_full && candidate.distance > furthest.distance {
                     break;
                 }
             }
 
+            // A selective filter keeps the list from filling up so
+            // the search would traverse the whole reachable graph.
+            if !is_full && self.visits >= self.visit_limit {
+                self.limited = true;
+                break;
+            }
+
             let layer_iter = layer.nearest_iter(&candidate.vector_id);
-            for vector_id in layer_iter.take(links) {
-                self.push(&vector_id, vector, vectors);
+            for vector_id in layer_iter {
+                self.push_filtered(&vector_id, vector, vectors, filter);
             }
 
             self.nearest.truncate(self.ef);
@@ -279,17 +490,36 @@ impl Search {
         &mut self,
         vector_id: &VectorID,
         vector: &Vector,
-        vectors: &HashMap<VectorID, Vector>,
+        vectors: &VectorStore,
+    ) {
+        self.push_filtered(vector_id, vector, vectors, &|_| true);
+    }
+
+    /// Pushes a new candidate to the search object. The candidate
+    /// is only added to the nearest neighbors if the filter allows
+    /// it but it is always considered for traversal.
+    pub fn push_filtered<F: Fn(&VectorID) -> bool>(
+        &mut self,
+        vector_id: &VectorID,
+        vector: &Vector,
+        vectors: &VectorStore,
+        filter: &F,
     ) {
         if !self.visited.insert(vector_id) {
             return;
         }
 
-        // Create a new candidate.
-        let other = &vectors[vector_id];
-        let distance = self.distance.calculate(vector, other);
-        let distance = OrderedFloat::from(distance);
+        // The vector might be deleted while it's still
+        // referenced by the neighbors of other nodes.
+        let (distance, table) = (&self.distance, self.table.as_ref());
+        let distance =
+            match vectors.distance(distance, vector, table, vector_id) {
+                Some(distance) => OrderedFloat::from(distance),
+                None => return,
+            };
 
+        // Create a new candidate.
+        self.visits += 1;
         let new = Candidate { distance, vector_id: *vector_id };
 
         // Make sure the index to insert to is within the EF scope.
@@ -299,10 +529,73 @@ impl Search {
             Ok(_) => unreachable!(),
         };
 
-        self.nearest.insert(index, new);
+        if filter(vector_id) {
+            self.nearest.insert(index, new);
+        }
+
         self.candidates.push(Reverse(new));
     }
 
+    /// Searches all graph layers from the entry point down to the
+    /// base layer. The filter only applies to the base layer because
+    /// the upper layers are used to navigate the graph.
+    /// * `base_layer`: Base layer of the graph.
+    /// * `upper_layers`: Upper layers of the graph from the lowest.
+    /// * `entry`: Vector ID to start the search from.
+    pub fn search_layers<'a, B: Layer, U, F: Fn(&VectorID) -> bool>(
+        &mut self,
+        base_layer: B,
+        upper_layers: &'a [Vec<U>],
+        entry: &VectorID,
+        vector: &Vector,
+        vectors: &VectorStore,
+        filter: &F,
+    ) where
+        &'a [U]: Layer,
+    {
+        let ef = self.ef;
+        self.prepare(vector, vectors);
+
+        if upper_layers.is_empty() {
+            self.push_filtered(entry, vector, vectors, filter);
+        } else {
+            self.push(entry, vector, vectors);
+        }
+
+        for layer in LayerID(upper_layers.len()).descend() {
+            if layer.is_zero() {
+                self.ef = ef;
+                self.retain(filter);
+                self.search_filtered(base_layer, vector, vectors, filter);
+
+                break;
+            }
+
+            // Layers without the entry node are skipped.
+            let layer = upper_layers[layer.0 - 1].as_slice();
+            if !layer.has_node(entry) {
+                continue;
+            }
+
+            self.ef = self.ef_upper;
+            self.search(layer, vector, vectors);
+            self.cull();
+        }
+    }
+
+    /// Prepares the search for the query vector. This must be
+    /// called before the search if the vector is new.
+    /// * `vector`: Vector to search.
+    /// * `vectors`: Stored vectors to search.
+    pub fn prepare(&mut self, vector: &Vector, vectors: &VectorStore) {
+        self.table = vectors.table(&self.distance, vector);
+    }
+
+    /// Removes the nearest neighbors not allowed by the filter.
+    pub fn retain<F: Fn(&VectorID) -> bool>(&mut self, filter: &F) {
+        self.nearest.retain(|candidate| filter(&candidate.vector_id));
+    }
+
     /// Lowers the search to the next lower layer.
     pub fn cull(&mut self) {
         self.candidates.clear();
@@ -323,48 +616,149 @@ impl Search {
         self.nearest.clear();
         self.working.clear();
         self.discarded.clear();
+        self.table = None;
     }
 
-    /// Selects the nearest neighbors.
-    pub fn select_simple(&mut self) -> &[Candidate] {
-        &self.nearest
+    /// Selects the neighbors of the searched vector from the nearest
+    /// candidates with the heuristic of the HNSW paper (algorithm 4).
+    /// * `layer`: Layer to extend the candidates from.
+    /// * `vector`: Vector of the search.
+    /// * `vectors`: Stored vectors of the nodes.
+    /// * `m`: Max number of neighbors to select.
+    /// * `extend`: Add the neighbors of the candidates as candidates.
+    /// * `keep_pruned`: Fill up to M with the discarded candidates.
+    pub fn select_heuristic<L: Layer>(
+        &mut self,
+        layer: L,
+        vector: &Vector,
+        vectors: &VectorStore,
+        m: usize,
+        extend: bool,
+        keep_pruned: bool,
+    ) -> Vec<Candidate> {
+        self.working.clear();
+        self.working.extend(self.nearest.iter().copied());
+
+        if extend {
+            let mut seen: HashSet<VectorID> =
+                self.nearest.iter().map(|c| c.vector_id).collect();
+
+            let table = self.table.as_ref();
+            for candidate in self.nearest.iter() {
+                for id in layer.nearest_iter(&candidate.vector_id) {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+
+                    let distance = &self.distance;
+                    let value = vectors.distance(distance, vector, table, &id);
+                    if let Some(value) = value {
+                        let distance = value.into();
+                        self.working
+                            .push(Candidate { distance, vector_id: id });
+                    }
+                }
+            }
+
+            self.working.sort();
+        }
+
+        self.select_working(vectors, m, keep_pruned)
     }
 
-    pub fn iter(&self) -> impl ExactSizeIterator<Item = Candidate> + '_ {
-        self.nearest.iter().copied()
+    /// Selects up to M neighbors of a node from the candidates with
+    /// the heuristic. It's used to prune the neighbors of a node that
+    /// has no free slot for a new neighbor.
+    /// * `candidates`: Candidates with their distance to the node.
+    /// * `vectors`: Stored vectors of the nodes.
+    /// * `m`: Max number of neighbors to select.
+    /// * `keep_pruned`: Fill up to M with the discarded candidates.
+    pub fn select_from(
+        &mut self,
+        candidates: impl Iterator<Item = Candidate>,
+        vectors: &VectorStore,
+        m: usize,
+        keep_pruned: bool,
+    ) -> Vec<Candidate> {
+        self.working.clear();
+        self.working.extend(candidates);
+        self.working.sort();
+        self.select_working(vectors, m, keep_pruned)
     }
-}
 
-impl Default for Search {
-    fn default() -> Self {
-        Self {
-            visited: Visited::with_capacity(0),
-            candidates: BinaryHeap::new(),
-            nearest: Vec::new(),
-            working: Vec::new(),
-            discarded: Vec::new(),
-            ef: 5,
-            distance: Distance::Euclidean,
+    /// Selects the neighbors from the sorted working candidates.
+    ///
+    /// A candidate is selected only if it's closer to the node than
+    /// to any of the selected neighbors. This spreads the neighbors
+    /// around the node instead of picking many from the same cluster
+    /// which keeps the clusters connected to each other.
+    fn select_working(
+        &mut self,
+        vectors: &VectorStore,
+        m: usize,
+        keep_pruned: bool,
+    ) -> Vec<Candidate> {
+        let mut selected: Vec<Candidate> = Vec::with_capacity(m);
+        self.discarded.clear();
+
+        for candidate in self.working.iter() {
+            if selected.len() >= m {
+                break;
+            }
+
+            let vector = match vectors.get(&candidate.vector_id) {
+                Some(vector) => vector,
+                None => continue,
+            };
+
+            let is_closer = selected.iter().all(|other| {
+                let id = &other.vector_id;
+                match vectors.distance(&self.distance, &vector, None, id) {
+                    Some(distance) => candidate.distance.0 < distance,
+                    None => true,
+                }
+            });
+
+            match is_closer {
+                true => selected.push(*candidate),
+                false => self.discarded.push(*candidate),
+            }
+        }
+
+        if keep_pruned {
+            let missing = m.saturating_sub(selected.len());
+            selected.extend(self.discarded.iter().take(missing));
+            selected.sort();
         }
+
+        selected
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = Candidate> + '_ {
+        self.nearest.iter().copied()
     }
 }
 
 pub struct SearchPool {
     pool: Mutex<Vec<(Search, Search)>>,
     len: usize,
+    distance: Distance,
 }
 
 impl SearchPool {
-    pub fn new(len: usize) -> Self {
+    pub fn new(len: usize, distance: Distance) -> Self {
         let pool = Mutex::new(Vec::new());
-        Self { pool, len }
+        Self { pool, len, distance }
     }
 
     /// Returns the last searches from the pool.
     pub fn pop(&self) -> (Search, Search) {
         match self.pool.lock().pop() {
             Some(result) => result,
-            None => (Search::new(self.len), Search::new(self.len)),
+            None => {
+                let search = Search::new(self.len, self.distance.clone());
+                (search.clone(), search)
+            }
         }
     }
 
@@ -376,87 +770,367 @@ impl SearchPool {
 
 pub struct IndexConstruction<'a> {
     pub search_pool: SearchPool,
+    /// Highest layer of the entry node.
     pub top_layer: LayerID,
+    /// Node to start the searches from or invalid without nodes.
+    pub entry: VectorID,
     pub base_layer: &'a [RwLock<BaseNode>],
-    pub vectors: &'a HashMap<VectorID, Vector>,
+    pub upper_layers: &'a [Vec<RwLock<UpperNode>>],
+    pub vectors: &'a VectorStore,
     pub config: &'a Config,
+    /// Change log to record the nodes whose neighbors change.
+    pub changes: Option<&'a ChangeLog>,
 }
 
 impl<'a> IndexConstruction<'a> {
-    /// Inserts a vector ID into a layer.
+    /// Links the node with the highest level before the others
+    /// if it's above the entry and makes it the new entry. Without
+    /// an entry, the node becomes the entry without being linked.
+    /// * `ids`: Vector IDs of the new nodes.
+    /// * `levels`: Highest layer of each node.
+    pub fn promote(&mut self, ids: &[VectorID], levels: &[LayerID]) {
+        let i = match (0..ids.len()).max_by_key(|i| levels[*i]) {
+            Some(i) => i,
+            None => return,
+        };
+
+        let (vector_id, level) = (ids[i], levels[i]);
+        if self.entry.is_valid() {
+            if level <= self.top_layer {
+                return;
+            }
+
+            self.insert(&vector_id, &level);
+        }
+
+        self.entry = vector_id;
+        self.top_layer = level;
+    }
+
+    /// Inserts a vector ID into the layers up to its level. Its
+    /// nodes in the upper layers must be added before inserting.
     /// * `vector_id`: Vector ID to insert.
-    /// * `layer`: Layer to insert into.
-    /// * `layers`: Upper layers.
-    pub fn insert(
-        &self,
-        vector_id: &VectorID,
-        layer: &LayerID,
-        layers: &[Vec<UpperNode>],
-    ) {
-        let vector = &self.vectors[vector_id];
+    /// * `level`: Highest layer of the node.
+    pub fn insert(&self, vector_id: &VectorID, level: &LayerID) {
+        if !self.entry.is_valid() || *vector_id == self.entry {
+            return;
+        }
 
-        let dist = self.config.distance;
+        let vector = match self.vectors.get(vector_id) {
+            Some(vector) => vector,
+            None => return,
+        };
 
         let (mut search, mut insertion) = self.search_pool.pop();
         insertion.ef = self.config.ef_construction;
 
-        // Find the first valid vector ID to push.
-        let validator = |i: usize| self.vectors.get(&i.into()).is_some();
-        let valid_id = (0..self.vectors.len())
-            .into_par_iter()
-            .find_first(|i| validator(*i))
-            .unwrap();
-
         search.reset();
-        search.push(&valid_id.into(), vector, self.vectors);
+        search.prepare(&vector, self.vectors);
+        search.push(&self.entry, &vector, self.vectors);
 
         for current_layer in self.top_layer.descend() {
-            if current_layer <= *layer {
+            if current_layer <= *level {
                 search.ef = self.config.ef_construction;
             }
 
-            // Find the nearest neighbor candidates.
-            if current_layer > *layer {
-                let layer = layers[current_layer.0 - 1].as_slice();
-                search.search(layer, vector, self.vectors, M);
-                search.cull();
-            } else {
-                search.search(self.base_layer, vector, self.vectors, M * 2);
+            // Find the nearest neighbor candidates and link the
+            // node to them in the layers it's in.
+            if current_layer.is_zero() {
+                let layer = self.base_layer;
+                search.search(layer, &vector, self.vectors);
+                self.link(
+                    layer,
+                    vector_id,
+                    &vector,
+                    &mut search,
+                    &mut insertion,
+                );
                 break;
             }
+
+            let layer = self.upper_layers[current_layer.0 - 1].as_slice();
+            search.search(layer, &vector, self.vectors);
+            if current_layer <= *level {
+                self.link(
+                    layer,
+                    vector_id,
+                    &vector,
+                    &mut search,
+                    &mut insertion,
+                );
+            }
+
+            search.cull();
         }
 
-        // Select the neighbors.
-        let candidates = {
-            let candidates = search.select_simple();
-            &candidates[..Ord::min(candidates.len(), M)]
-        };
+        self.search_pool.push(&(search, insertion));
+    }
+
+    /// Links the node to the neighbors selected from the nearest
+    /// candidates of the search and the neighbors back to it.
+    /// * `layer`: Layer to link the node in.
+    /// * `vector_id`: Vector ID of the node.
+    /// * `vector`: Vector of the node.
+    /// * `search`: Search with the nearest candidates in the layer.
+    /// * `insertion`: Search used to prune the neighbors.
+    fn link<N: Node>(
+        &self,
+        layer: &[RwLock<N>],
+        vector_id: &VectorID,
+        vector: &Vector,
+        search: &mut Search,
+        insertion: &mut Search,
+    ) {
+        let dist = &self.config.distance;
+
+        // Select the neighbors other than the node itself. The node
+        // keeps M of them and the rest of the slots are left for the
+        // nodes that link to it later.
+        search.retain(&|id| id != vector_id);
+        let m = self.config.m.min(layer[vector_id].read().len());
+        let candidates = search.select_heuristic(
+            layer,
+            vector,
+            self.vectors,
+            m,
+            self.config.extend_candidates,
+            self.config.keep_pruned_connections,
+        );
 
         for (i, candidate) in candidates.iter().enumerate() {
             let vid = candidate.vector_id;
-            let old = &self.vectors[&vid];
+            let old = match self.vectors.get(&vid) {
+                Some(vector) => vector,
+                None => continue,
+            };
+
             let distance = candidate.distance;
+            let table = self.vectors.table(dist, &old);
+            let to_old = |id: &VectorID| {
+                self.vectors.distance(dist, &old, table.as_ref(), id)
+            };
 
-            // Function to sort the vectors by distance.
-            let ordering = |id: &VectorID| {
-                if !id.is_valid() {
-                    Ordering::Greater
-                } else {
-                    let other = &self.vectors[id];
-                    distance.cmp(&dist.calculate(old, other).into())
+            // The node is unlocked before locking the new node
+            // so the nodes are never locked at the same time.
+            {
+                let mut node = layer[&vid].write();
+                match node.last().is_some_and(|id| id.is_valid()) {
+                    // Without a free slot, the neighbors including the
+                    // new node are pruned with the heuristic.
+                    true => {
+                        let iter = node.iter().filter_map(|id| {
+                            let distance = to_old(id)?.into();
+                            Some(Candidate { distance, vector_id: *id })
+                        });
+
+                        let new = Candidate { distance, vector_id: *vector_id };
+                        let neighbors = insertion.select_from(
+                            iter.chain(std::iter::once(new)),
+                            self.vectors,
+                            node.len(),
+                            self.config.keep_pruned_connections,
+                        );
+
+                        node.allocate(neighbors.iter().map(|c| c.vector_id));
+                    }
+                    false => {
+                        // Function to sort the vectors by distance.
+                        // Invalid and deleted vector IDs are sorted
+                        // to the end.
+                        let ordering = |id: &VectorID| match to_old(id) {
+                            Some(other) => OrderedFloat(other).cmp(&distance),
+                            None => Ordering::Greater,
+                        };
+
+                        // Find the index to insert at to keep the order.
+                        let index = node
+                            .binary_search_by(ordering)
+                            .unwrap_or_else(|error| error);
+
+                        node.insert(index, vector_id);
+                    }
                 }
-            };
+            }
 
-            // Find the correct index to insert at to keep the order.
-            let index = self.base_layer[&vid]
-                .read()
-                .binary_search_by(ordering)
-                .unwrap_or_else(|error| error);
+            layer[vector_id].write().set(i, &vid);
+        }
 
-            self.base_layer[&vid].write().insert(index, vector_id);
-            self.base_layer[vector_id].write().set(i, vector_id);
+        if let Some(changes) = self.changes {
+            let touched = candidates.iter().map(|c| c.vector_id);
+            let touched: Vec<VectorID> =
+                touched.chain(std::iter::once(*vector_id)).collect();
+            changes.record(&[], &touched);
         }
+    }
+}
 
-        self.search_pool.push(&(search, insertion));
+/// Removes the deleted vector IDs from a graph layer.
+///
+/// The nodes linking to a deleted node are reconnected to its
+/// neighbors so nodes only reachable through it stay reachable.
+/// The links are mostly mutual so these nodes are looked up among
+/// the neighbors of the deleted node and their neighbors instead
+/// of the whole layer. Links to deleted nodes missed by the lookup
+/// are skipped by the searches and pruned when the node changes.
+///
+/// The neighbors are selected with the heuristic like the
+/// neighbors of an inserted node and kept sorted by distance
+/// without gaps because the traversal stops at the first invalid
+/// neighbor. Only one node is locked at a time.
+///
+/// Returns the vector IDs of the nodes whose neighbors changed.
+/// * `layer`: Nodes of the graph layer.
+/// * `ids`: Vector IDs to remove from the layer.
+/// * `vectors`: Vectors of the nodes remaining in the layer.
+/// * `config`: Config of the collection the graph was built with.
+pub fn unlink<L: LayerMut + ?Sized>(
+    layer: &mut L,
+    ids: &[VectorID],
+    vectors: &VectorStore,
+    config: &Config,
+) -> Vec<VectorID> {
+    let valid = |node: &L::Node| -> Vec<VectorID> {
+        node.iter().take_while(|n| n.is_valid()).copied().collect()
+    };
+
+    // Take the neighbors of the deleted nodes as the
+    // replacement candidates and clear the deleted nodes.
+    let mut deleted = HashMap::new();
+    for id in ids {
+        let clear = |node: &mut L::Node| {
+            let neighbors = valid(node);
+            node.as_mut().fill(INVALID);
+            neighbors
+        };
+
+        if let Some(neighbors) = layer.update(id, clear) {
+            deleted.insert(*id, neighbors);
+        }
+    }
+
+    // The nodes that might link to the deleted nodes.
+    let mut affected = HashSet::new();
+    for neighbors in deleted.values() {
+        for neighbor in neighbors {
+            if !affected.insert(*neighbor) {
+                continue;
+            }
+
+            let others = layer.update(neighbor, |node| valid(node));
+            affected.extend(others.into_iter().flatten());
+        }
+    }
+
+    let distance = &config.distance;
+    let mut search = Search::new(0, distance.clone());
+    let mut changed = vec![];
+    for id in affected.iter().filter(|id| !deleted.contains_key(id)) {
+        let vector = match vectors.get(id) {
+            Some(vector) => vector,
+            None => continue,
+        };
+
+        let table = vectors.table(distance, &vector);
+        let to_candidate = |other: &VectorID| {
+            let value =
+                vectors.distance(distance, &vector, table.as_ref(), other);
+            Some(Candidate { distance: value?.into(), vector_id: *other })
+        };
+
+        let repair = |node: &mut L::Node| {
+            let neighbors = valid(node);
+            if !neighbors.iter().any(|n| deleted.contains_key(n)) {
+                return false;
+            }
+
+            // Collect the remaining and replacement neighbors.
+            let mut candidates: Vec<VectorID> = vec![];
+            for neighbor in neighbors.iter() {
+                let replacements = match deleted.get(neighbor) {
+                    Some(replacements) => replacements.as_slice(),
+                    None => std::slice::from_ref(neighbor),
+                };
+
+                for other in replacements {
+                    let is_new = other != id && !candidates.contains(other);
+                    if is_new && !deleted.contains_key(other) {
+                        candidates.push(*other);
+                    }
+                }
+            }
+
+            let selected = search.select_from(
+                candidates.iter().filter_map(to_candidate),
+                vectors,
+                node.len(),
+                config.keep_pruned_connections,
+            );
+
+            node.allocate(selected.iter().map(|c| c.vector_id));
+            true
+        };
+
+        if layer.update(id, repair) == Some(true) {
+            changed.push(*id);
+        }
+    }
+
+    changed
+}
+
+/// The persisted collection that the changes are relative to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub path: String,
+    pub name: String,
+    pub version: u64,
+}
+
+/// The changes made to a collection since it was persisted.
+#[derive(Debug, Clone, Default)]
+pub struct Changes {
+    /// None if the collection is not persisted yet.
+    pub origin: Option<Origin>,
+    /// Records whose vector or data changed.
+    pub records: HashSet<VectorID>,
+    /// Graph nodes whose neighbors changed in any layer.
+    pub nodes: HashSet<VectorID>,
+}
+
+/// The change log of a collection. It uses a mutex so the changes
+/// can be cleared when saving the collection by reference.
+#[derive(Debug, Default)]
+pub struct ChangeLog(Mutex<Changes>);
+
+impl ChangeLog {
+    /// Records the changed records and graph nodes.
+    /// Changes are not tracked until the collection is persisted
+    /// because it has to be written as a whole anyway.
+    pub fn record(&self, records: &[VectorID], nodes: &[VectorID]) {
+        let mut changes = self.0.lock();
+        if changes.origin.is_none() {
+            return;
+        }
+
+        changes.records.extend(records);
+        changes.nodes.extend(nodes);
+    }
+
+    /// Returns true if the collection changed since it was persisted
+    /// or if it's not persisted yet.
+    pub fn is_pending(&self) -> bool {
+        let changes = self.0.lock();
+        let changed = !changes.records.is_empty() || !changes.nodes.is_empty();
+        changes.origin.is_none() || changed
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, Changes> {
+        self.0.lock()
+    }
+}
+
+impl Clone for ChangeLog {
+    fn clone(&self) -> Self {
+        Self(Mutex::new(self.0.lock().clone()))
     }
 }
@@ -108,3 +108,317 @@ impl From<Vector> for Vec<f32> {
         vector.0
     }
 }
+
+impl FromIterator<f32> for Vector {
+    fn from_iter<T: IntoIterator<Item = f32>>(iter: T) -> Self {
+        Vector(iter.into_iter().collect())
+    }
+}
+
+/// The vectors of a collection. With quantization, the vectors are
+/// stored quantized and the full precision vectors are only kept
+/// when they're needed to re-rank the search results or when the
+/// quantizer is not trained yet.
+///
+/// For the cosine similarity, the vectors are stored normalized
+/// with their norms so the similarity is a dot product.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub(crate) struct VectorStore {
+    pub(crate) floats: HashMap<VectorID, Vector>,
+    pub(crate) codes: HashMap<VectorID, Code>,
+    pub(crate) norms: HashMap<VectorID, f32>,
+    pub(crate) quantizer: Option<Quantizer>,
+    pub(crate) rerank: bool,
+    pub(crate) normalized: bool,
+}
+
+impl VectorStore {
+    /// Creates an empty vector store for the collection config.
+    pub fn new(config: &Config) -> Self {
+        let quantizer = Quantizer::new(config);
+        let rerank = config.rerank;
+        let normalized = config.distance == Distance::Cosine;
+        Self { quantizer, rerank, normalized, ..Default::default() }
+    }
+
+    /// Returns the vector with a norm of 1 if the stored vectors
+    /// are normalized. Vectors with a norm of 0 are unchanged.
+    /// * `vector`: Vector to normalize, e.g. a query.
+    pub fn normalize<'a>(&self, vector: &'a Vector) -> Cow<'a, Vector> {
+        let norm = Kernel::detect().dot(&vector.0, &vector.0).sqrt();
+        match self.normalized && norm > 0.0 {
+            true => Cow::Owned(vector.0.iter().map(|x| x / norm).collect()),
+            false => Cow::Borrowed(vector),
+        }
+    }
+
+    /// Returns the distance function to calculate on the stored
+    /// vectors. The cosine similarity of normalized vectors is
+    /// their dot product.
+    /// * `distance`: Distance function of the collection.
+    fn metric<'a>(&self, distance: &'a Distance) -> &'a Distance {
+        match (self.normalized, distance) {
+            (true, Distance::Cosine) => &Distance::Dot,
+            _ => distance,
+        }
+    }
+
+    /// Returns the quantizer if the vectors are quantized.
+    pub fn quantized(&self) -> Option<&Quantizer> {
+        self.quantizer.as_ref().filter(|q| q.is_trained())
+    }
+
+    /// Returns true if the full precision vectors are stored.
+    pub fn keeps_floats(&self) -> bool {
+        self.quantized().is_none() || self.rerank
+    }
+
+    /// Fits the quantizer to the vectors before they're inserted.
+    /// The scalar quantizer is fitted once the stored vectors with
+    /// the new ones reach the fit sample. Until then, the vectors
+    /// are stored in full precision. Returns true if the stored
+    /// vectors are quantized.
+    /// * `vectors`: Vectors that will be inserted.
+    pub fn fit<'a>(
+        &mut self,
+        vectors: impl Iterator<Item = &'a Vector>,
+    ) -> bool {
+        let vectors: Vec<Cow<Vector>> =
+            vectors.map(|vector| self.normalize(vector)).collect();
+        let vectors = vectors.iter().map(|vector| vector.as_ref());
+
+        match self.quantizer.as_mut() {
+            Some(Quantizer::Binary(quantizer)) => quantizer.fit(vectors),
+            Some(Quantizer::Scalar(quantizer)) if !quantizer.is_fitted() => {
+                if self.floats.len() + vectors.len() < FIT_SAMPLE {
+                    return false;
+                }
+
+                quantizer.fit(self.floats.values().chain(vectors));
+                self.quantize();
+                return true;
+            }
+            _ => {}
+        }
+
+        false
+    }
+
+    /// Quantizes the stored full precision vectors and drops them
+    /// if they're not needed to re-rank the search results.
+    fn quantize(&mut self) {
+        let quantizer = match self.quantized() {
+            Some(quantizer) => quantizer,
+            None => return,
+        };
+
+        let floats = self.floats.par_iter();
+        self.codes = floats.map(|(id, v)| (*id, quantizer.encode(v))).collect();
+        if !self.keeps_floats() {
+            self.floats = HashMap::new();
+        }
+    }
+
+    /// Fits the scalar quantizer or trains the product quantizer on
+    /// the stored vectors and quantizes them. Does nothing for other
+    /// quantizations. Returns true if the stored vectors are
+    /// quantized again.
+    pub fn train(&mut self) -> Result<bool, Error> {
+        let mut quantizer = match &mut self.quantizer {
+            Some(Quantizer::Product(quantizer)) => quantizer.clone(),
+            Some(Quantizer::Scalar(quantizer)) if !quantizer.is_fitted() => {
+                if self.floats.is_empty() {
+                    return Ok(false);
+                }
+
+                quantizer.fit(self.floats.values());
+                self.quantize();
+                return Ok(true);
+            }
+            _ => return Ok(false),
+        };
+
+        let ids: Vec<VectorID> = self.keys().copied().collect();
+        let vectors: Vec<Cow<Vector>> =
+            ids.iter().map(|id| self.get(id).unwrap()).collect();
+
+        let refs: Vec<&Vector> = vectors.iter().map(|v| v.as_ref()).collect();
+        quantizer.train(&refs)?;
+
+        let iter = ids.par_iter().zip(refs.par_iter());
+        let codes = iter.map(|(id, v)| (*id, quantizer.encode(v))).collect();
+
+        drop(refs);
+        drop(vectors);
+
+        self.codes = codes;
+        self.quantizer = Some(Quantizer::Product(quantizer));
+        if !self.keeps_floats() {
+            self.floats = HashMap::new();
+        }
+
+        Ok(true)
+    }
+
+    /// Stores the vector of the vector ID.
+    /// * `id`: Vector ID of the vector.
+    /// * `vector`: Vector to store.
+    pub fn insert(&mut self, id: VectorID, vector: &Vector) {
+        if self.normalized {
+            let norm = Kernel::detect().dot(&vector.0, &vector.0).sqrt();
+            self.norms.insert(id, norm);
+        }
+
+        let vector = self.normalize(vector);
+        if let Some(quantizer) = self.quantized() {
+            self.codes.insert(id, quantizer.encode(&vector));
+        }
+
+        if self.keeps_floats() {
+            self.floats.insert(id, vector.into_owned());
+        }
+    }
+
+    /// Removes the vector of the vector ID.
+    /// * `id`: Vector ID to remove.
+    pub fn remove(&mut self, id: &VectorID) {
+        self.floats.remove(id);
+        self.codes.remove(id);
+        self.norms.remove(id);
+    }
+
+    /// Returns the vector of the vector ID. The vector is decoded
+    /// if the full precision vector is not stored.
+    /// * `id`: Vector ID to retrieve.
+    pub fn get(&self, id: &VectorID) -> Option<Cow<'_, Vector>> {
+        if let Some(vector) = self.floats.get(id) {
+            return Some(Cow::Borrowed(vector));
+        }
+
+        let quantizer = self.quantized()?;
+        let code = self.codes.get(id)?;
+        Some(Cow::Owned(quantizer.decode(code)))
+    }
+
+    /// Returns the vector of the vector ID as it was inserted,
+    /// scaled back to its norm if the stored vector is normalized.
+    /// * `id`: Vector ID to retrieve.
+    pub fn original(&self, id: &VectorID) -> Option<Vector> {
+        let vector = self.get(id)?;
+        match self.norms.get(id) {
+            Some(norm) if *norm > 0.0 => {
+                Some(vector.0.iter().map(|x| x * norm).collect())
+            }
+            _ => Some(vector.into_owned()),
+        }
+    }
+
+    /// Returns the distance table of the query if the distances
+    /// to the quantized vectors can be looked up from it.
+    /// * `distance`: Distance function to use.
+    /// * `vector`: Query vector.
+    pub fn table(
+        &self,
+        distance: &Distance,
+        vector: &Vector,
+    ) -> Option<DistanceTable> {
+        self.quantized()?.table(self.metric(distance), vector)
+    }
+
+    /// Calculates the distance between the vector and the stored
+    /// vector where lower is closer for all distance functions.
+    /// The quantized vector is used if it's quantized.
+    /// * `distance`: Distance function to use.
+    /// * `vector`: Full precision vector, normalized like the stored.
+    /// * `table`: Distance table of the vector if any.
+    /// * `id`: Vector ID of the stored vector.
+    pub fn distance(
+        &self,
+        distance: &Distance,
+        vector: &Vector,
+        table: Option<&DistanceTable>,
+        id: &VectorID,
+    ) -> Option<f32> {
+        let distance = self.metric(distance);
+        let value = match self.quantized() {
+            Some(quantizer) => {
+                let code = self.codes.get(id)?;
+                quantizer.distance(distance, vector, table, code)
+            }
+            None => distance.calculate(vector, self.floats.get(id)?),
+        };
+
+        Some(distance.to_distance(value))
+    }
+
+    /// Re-calculates the distances of the candidates with the full
+    /// precision vectors if they're quantized and sorts them again.
+    /// * `distance`: Distance function to use.
+    /// * `vector`: Full precision vector.
+    /// * `candidates`: Candidates with the quantized distances.
+    pub(crate) fn rerank(
+        &self,
+        distance: &Distance,
+        vector: &Vector,
+        candidates: &mut [Candidate],
+    ) {
+        if self.quantized().is_none() || !self.rerank {
+            return;
+        }
+
+        let distance = self.metric(distance);
+        for candidate in candidates.iter_mut() {
+            let other = &self.floats[&candidate.vector_id];
+            let value = distance.calculate(vector, other);
+            candidate.distance = distance.to_distance(value).into();
+        }
+
+        candidates.sort();
+    }
+
+    /// Returns the vector IDs of the stored vectors.
+    pub fn keys(&self) -> Box<dyn Iterator<Item = &VectorID> + '_> {
+        match self.quantized() {
+            Some(_) => Box::new(self.codes.keys()),
+            None => Box::new(self.floats.keys()),
+        }
+    }
+
+    /// Checks if the vector of the vector ID is stored.
+    /// * `id`: Vector ID to check.
+    pub fn contains_key(&self, id: &VectorID) -> bool {
+        match self.quantized() {
+            Some(_) => self.codes.contains_key(id),
+            None => self.floats.contains_key(id),
+        }
+    }
+
+    /// Returns the number of stored vectors.
+    pub fn len(&self) -> usize {
+        match self.quantized() {
+            Some(_) => self.codes.len(),
+            None => self.floats.len(),
+        }
+    }
+
+    /// Returns true if no vectors are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the store with the vector IDs changed by the function.
+    /// * `f`: Function returning the new vector ID.
+    pub fn map_ids(self, f: impl Fn(&VectorID) -> VectorID) -> Self {
+        let floats = self.floats.into_iter().map(|(id, v)| (f(&id), v));
+        let codes = self.codes.into_iter().map(|(id, c)| (f(&id), c));
+        let norms = self.norms.into_iter().map(|(id, n)| (f(&id), n));
+        Self {
+            floats: floats.collect(),
+            codes: codes.collect(),
+            norms: norms.collect(),
+            quantizer: self.quantizer,
+            rerank: self.rerank,
+            normalized: self.normalized,
+        }
+    }
+}
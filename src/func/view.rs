@@ -0,0 +1,264 @@
+use super::*;
+
+/// The records and the graph of a collection borrowed for reading.
+/// Both collection types look up and search their records through
+/// it so they behave the same way.
+pub(crate) struct CollectionView<'a, B, U> {
+    pub config: &'a Config,
+    pub relevancy: f32,
+    pub data: &'a HashMap<VectorID, Metadata>,
+    pub vectors: &'a VectorStore,
+    pub slots: &'a [VectorID],
+    pub indexes: &'a HashMap<String, FieldIndex>,
+    pub keys: &'a HashMap<VectorID, RecordKey>,
+    pub key_ids: &'a HashMap<RecordKey, VectorID>,
+    pub sparse: &'a SparseIndex,
+    pub count: usize,
+    pub dimension: usize,
+    pub base_layer: B,
+    pub upper_layers: &'a [Vec<U>],
+    /// The live node in the highest layer to start the searches from.
+    pub entry: VectorID,
+}
+
+impl<'a, B: Layer + Copy, U> CollectionView<'a, B, U>
+where
+    &'a [U]: Layer,
+{
+    /// Validates a vector dimension against the collection's.
+    pub fn validate_dimension(&self, vector: &Vector) -> Result<(), Error> {
+        let found = vector.len();
+        let expected = self.dimension;
+
+        if found != expected {
+            Err(Error::invalid_dimension(found, expected))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the stored record of the vector ID.
+    pub fn record(&self, id: &VectorID) -> Record {
+        Record {
+            vector: self.vectors.original(id).unwrap(),
+            data: self.data[id].clone(),
+            key: self.keys.get(id).cloned(),
+            sparse: self.sparse.get(id).cloned(),
+        }
+    }
+
+    /// Returns the stored record associated with the key.
+    pub fn record_by_key(&self, key: &RecordKey) -> Result<Record, Error> {
+        match self.key_ids.get(key) {
+            Some(id) => Ok(self.record(id)),
+            None => Err(Error::key_not_found()),
+        }
+    }
+
+    /// Creates the search result of the vector ID.
+    pub fn search_result(&self, id: &VectorID, distance: f32) -> SearchResult {
+        let data = self.data[id].clone();
+        let key = self.keys.get(id).cloned();
+        SearchResult { id: id.0, distance, data, key, vector: None }
+    }
+
+    /// Searches the collection for the nearest neighbors
+    /// whose metadata matches the filter.
+    pub fn search_with_filter(
+        &self,
+        vector: &Vector,
+        n: usize,
+        filter: &Filter,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let allow = |id: &VectorID| match self.data.get(id) {
+            Some(data) => filter.matches(data),
+            None => false,
+        };
+
+        // Without usable indexes, the filter is applied
+        // while traversing the graph.
+        let params = SearchParams::default();
+        let candidates = match plan(self.indexes, filter) {
+            Some(candidates) => candidates,
+            None => return self.search_layers(vector, n, &params, &allow),
+        };
+
+        // When only a few records might match, scanning them is
+        // faster and more accurate than traversing the graph.
+        let threshold = self.count as f32 * BRUTE_FORCE_RATIO;
+        if candidates.len() as f32 <= threshold {
+            let ids = candidates.iter();
+            return self.search_exhaustive(vector, n, ids, &params, &allow);
+        }
+
+        let allow_candidate =
+            |id: &VectorID| candidates.contains(id) && allow(id);
+
+        self.search_layers(vector, n, &params, &allow_candidate)
+    }
+
+    /// Searches the collection for the records whose sparse vectors
+    /// have the highest dot product with the query.
+    pub fn sparse_search(
+        &self,
+        sparse: &SparseVector,
+        n: usize,
+    ) -> Vec<SearchResult> {
+        let scores = self.sparse.search(sparse, n);
+        let results =
+            scores.iter().map(|(id, score)| self.search_result(id, *score));
+
+        results.collect()
+    }
+
+    /// Searches the collection with both the dense and the sparse
+    /// vector and combines their results with the fusion method.
+    pub fn hybrid_search(
+        &self,
+        vector: &Vector,
+        sparse: &SparseVector,
+        n: usize,
+        fusion: &Fusion,
+    ) -> Result<Vec<SearchResult>, Error> {
+        // Both searches return more records than needed so the
+        // records ranked lower by one of them can still make it.
+        let k = n.max(self.config.ef_search);
+        let params = SearchParams::default();
+        let dense = self.search_layers(vector, k, &params, &|_| true)?;
+        let dense: Vec<VectorID> = dense.iter().map(|r| r.id.into()).collect();
+        let scores = self.sparse.search(sparse, k);
+        let scores: Vec<VectorID> = scores.iter().map(|(id, _)| *id).collect();
+
+        let query = &self.vectors.normalize(vector);
+        let distance = &self.config.distance;
+        let table = self.vectors.table(distance, query);
+        let dense_score = |id: &VectorID| {
+            let table = table.as_ref();
+            -self.vectors.distance(distance, query, table, id).unwrap()
+        };
+
+        let sparse_score = |id: &VectorID| self.sparse.score(sparse, id);
+        let fused = fusion.fuse(&dense, &scores, dense_score, sparse_score);
+        let results = fused
+            .iter()
+            .take(n)
+            .map(|(id, score)| self.search_result(id, *score));
+
+        Ok(results.collect())
+    }
+
+    /// Searches the index layers for the nearest neighbors
+    /// allowed by the filter function.
+    pub fn search_layers<F: Fn(&VectorID) -> bool>(
+        &self,
+        vector: &Vector,
+        n: usize,
+        params: &SearchParams,
+        filter: &F,
+    ) -> Result<Vec<SearchResult>, Error> {
+        // Early return if the collection is empty.
+        if self.vectors.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Ensure the vector dimension matches the collection dimension.
+        self.validate_dimension(vector)?;
+        let query = &self.vectors.normalize(vector);
+
+        // Start from the live node in the highest layer.
+        let vector_id = self.entry;
+        if !vector_id.is_valid() {
+            return Err("Unable to initiate search.".into());
+        }
+
+        let distance = &self.config.distance;
+        let mut search = Search::new(self.slots.len(), distance.clone());
+        search.ef = params.ef_search.unwrap_or(self.config.ef_search);
+        search.ef_upper = params.ef_upper.unwrap_or(search.ef_upper);
+
+        let limit = (self.count as f32 * FILTER_VISIT_RATIO) as usize;
+        search.visit_limit = limit.max(search.ef);
+        search.search_layers(
+            self.base_layer,
+            self.upper_layers,
+            &vector_id,
+            query,
+            self.vectors,
+            filter,
+        );
+
+        // The filter allows too few of the visited nodes to fill the
+        // nearest neighbors so the allowed records are scanned instead.
+        if search.limited {
+            let ids = self.vectors.keys();
+            return self.search_exhaustive(vector, n, ids, params, filter);
+        }
+
+        // Re-rank the candidates if their distances are approximate.
+        let mut candidates: Vec<Candidate> = search.iter().collect();
+        self.vectors.rerank(distance, query, &mut candidates);
+        Ok(self.search_results(&candidates, n, params))
+    }
+
+    /// Calculates the distance between the query and each
+    /// record allowed by the filter to find the true nearest.
+    pub fn search_exhaustive<'i, F: Fn(&VectorID) -> bool>(
+        &self,
+        vector: &Vector,
+        n: usize,
+        ids: impl Iterator<Item = &'i VectorID>,
+        params: &SearchParams,
+        filter: &F,
+    ) -> Result<Vec<SearchResult>, Error> {
+        let mut candidates = vec![];
+
+        // Ensure the vector dimension matches the collection dimension.
+        self.validate_dimension(vector)?;
+        let vector = &self.vectors.normalize(vector);
+
+        // Calculate the distance between the query and each record.
+        let distance = &self.config.distance;
+        let table = self.vectors.table(distance, vector);
+        for id in ids.filter(|id| filter(id)) {
+            let table = table.as_ref();
+            let value = self.vectors.distance(distance, vector, table, id);
+            let value = value.unwrap();
+            let candidate =
+                Candidate { distance: value.into(), vector_id: *id };
+            candidates.push(candidate);
+        }
+
+        // Sort the nearest neighbors by distance.
+        candidates.sort();
+        self.vectors.rerank(distance, vector, &mut candidates);
+        Ok(self.search_results(&candidates, n, params))
+    }
+
+    /// Creates the search results of the sorted candidates with
+    /// the parameters of the search.
+    fn search_results(
+        &self,
+        candidates: &[Candidate],
+        n: usize,
+        params: &SearchParams,
+    ) -> Vec<SearchResult> {
+        let distance = &self.config.distance;
+        let map_result = |candidate: &Candidate| {
+            let id = &candidate.vector_id;
+            let value = distance.from_distance(candidate.distance.0);
+            let mut result = self.search_result(id, value);
+            if params.include_vectors {
+                result.vector = self.vectors.original(id);
+            }
+
+            result
+        };
+
+        // Get relevant results and truncate the list.
+        let res = candidates.iter().map(map_result).collect();
+        let relevancy = params.relevancy.unwrap_or(self.relevancy);
+        let mut relevant = truncate_irrelevant(res, relevancy, distance);
+        relevant.truncate(n);
+        relevant
+    }
+}
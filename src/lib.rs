@@ -1,4 +1,5 @@
 #![allow(clippy::needless_doctest_main)]
+#![allow(non_local_definitions)]
 #![warn(missing_docs)]
 #![doc = include_str!("../readme.md")]
 #![doc(html_favicon_url = "https://i.postimg.cc/W3T230zk/favicon.png")]
@@ -15,9 +16,16 @@ pub mod prelude;
 
 pub use db::database;
 pub use func::collection;
+pub use func::concurrent;
 pub use func::distance;
 pub use func::err;
+pub use func::filter;
+pub use func::index;
+pub use func::key;
 pub use func::metadata;
+pub use func::quantization;
+pub use func::simd;
+pub use func::sparse;
 pub use func::vector;
 
 use pyo3::prelude::*;
@@ -52,7 +60,9 @@ fn collection_modules(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<collection::Config>()?;
     m.add_class::<collection::Record>()?;
     m.add_class::<collection::Collection>()?;
+    m.add_class::<collection::SearchParams>()?;
     m.add_class::<collection::SearchResult>()?;
+    m.add_class::<collection::UpsertResult>()?;
     Ok(())
 }
 
@@ -60,12 +70,14 @@ fn collection_modules(_py: Python, m: &PyModule) -> PyResult<()> {
 fn vector_modules(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<vector::Vector>()?;
     m.add_class::<vector::VectorID>()?;
+    m.add_class::<sparse::SparseVector>()?;
     Ok(())
 }
 
 #[pymodule]
 fn database_modules(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<database::Database>()?;
+    m.add_class::<database::CollectionHandle>()?;
     Ok(())
 }
 
@@ -74,9 +86,13 @@ fn prelude_modules(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<collection::Config>()?;
     m.add_class::<collection::Record>()?;
     m.add_class::<collection::Collection>()?;
+    m.add_class::<collection::SearchParams>()?;
     m.add_class::<collection::SearchResult>()?;
+    m.add_class::<collection::UpsertResult>()?;
     m.add_class::<vector::Vector>()?;
     m.add_class::<vector::VectorID>()?;
+    m.add_class::<sparse::SparseVector>()?;
     m.add_class::<database::Database>()?;
+    m.add_class::<database::CollectionHandle>()?;
     Ok(())
 }
@@ -1,6 +1,12 @@
 pub use crate::database::*;
 pub use crate::func::collection::*;
+pub use crate::func::concurrent::*;
 pub use crate::func::distance::*;
 pub use crate::func::err::*;
+pub use crate::func::filter::*;
+pub use crate::func::index::*;
+pub use crate::func::key::*;
 pub use crate::func::metadata::*;
+pub use crate::func::quantization::*;
+pub use crate::func::sparse::*;
 pub use crate::func::vector::*;
@@ -1,8 +1,17 @@
 mod test_collection;
+mod test_concurrent;
 mod test_database;
 mod test_distance;
+mod test_filter;
+mod test_index;
+mod test_key;
+mod test_quantization;
+mod test_simd;
+mod test_sparse;
 
 use crate::prelude::*;
+use crate::quantization::FIT_SAMPLE;
+use crate::simd::Kernel;
 use rayon::iter::*;
 use std::collections::HashMap;
 
@@ -1,4 +1,7 @@
 use super::*;
+use crate::func::utils::*;
+use crate::vector::VectorStore;
+use ordered_float::OrderedFloat;
 
 #[test]
 fn build_large() {
@@ -9,6 +12,31 @@ fn build_large() {
     assert_eq!(collection.len(), len);
 }
 
+#[test]
+fn build_with_custom_m() {
+    let config = Config { m: 8, m0: 16, ..Default::default() };
+    let records = Record::many_random(DIMENSION, 1000);
+    let mut collection = Collection::build(&config, &records).unwrap();
+    collection.insert(&Record::random(DIMENSION)).unwrap();
+
+    // The nodes are sized by the configured number of neighbors.
+    // The records not in an upper layer have empty nodes in it.
+    assert!(!collection.upper_layers.is_empty());
+    assert!(collection.base_layer.iter().all(|node| node.0.len() == 16));
+    let mut upper = collection.upper_layers.iter().flatten();
+    assert!(upper.all(|node| node.0.is_empty() || node.0.len() == 8));
+
+    // The records are still found by their own vectors.
+    let found = (0..20)
+        .filter(|i| {
+            let result = collection.search(&records[*i].vector, 1).unwrap();
+            result[0].id == *i as u32
+        })
+        .count();
+
+    assert!(found >= 18);
+}
+
 #[test]
 fn insert() {
     let mut collection = create_collection();
@@ -33,7 +61,19 @@ fn insert_invalid_dimension() {
 
     // Assert the new record is not inserted.
     assert_eq!(collection.dimension(), DIMENSION);
-    assert_eq!(collection.insert(&new_record).is_err(), true);
+    assert!(collection.insert(&new_record).is_err());
+}
+
+#[test]
+fn search_invalid_dimension() {
+    let mut collection = create_collection();
+    let record = Record::random(DIMENSION + 1);
+
+    // The dimension is validated before any distance is calculated.
+    let query = &record.vector;
+    assert!(collection.search(query, 5).is_err());
+    assert!(collection.true_search(query, 5).is_err());
+    assert!(collection.update(&VectorID(0), &record).is_err());
 }
 
 #[test]
@@ -77,6 +117,127 @@ fn delete() {
     assert_eq!(collection.len(), LEN - 1);
 }
 
+#[test]
+fn delete_with_churn() {
+    let config = Config::default();
+    let records = Record::many_random(DIMENSION, 500);
+    let mut collection = Collection::build(&config, &records).unwrap();
+
+    // Delete every other record.
+    for i in (0..500).step_by(2) {
+        collection.delete(&VectorID::from(i as usize)).unwrap();
+    }
+
+    // Only the nodes near a deleted record are repaired so a few
+    // links to the deleted records are left until they're pruned.
+    let base = collection.base_layer.iter().flat_map(|node| node.iter());
+    let upper = collection.upper_layers.iter().flatten();
+    let links: Vec<&VectorID> = base
+        .chain(upper.flat_map(|node| node.0.iter()))
+        .filter(|neighbor| neighbor.is_valid())
+        .collect();
+
+    let stale = links.iter().filter(|id| !collection.contains(id)).count();
+    assert!(stale * 20 < links.len(), "{stale} of {}", links.len());
+
+    // The nearest neighbor should still be found most of the time.
+    let found = (0..20)
+        .filter(|_| {
+            let query = Vector::random(DIMENSION);
+            let result = collection.search(&query, 1).unwrap();
+            let truth = collection.true_search(&query, 1).unwrap();
+            result[0].id == truth[0].id
+        })
+        .count();
+
+    assert!(found >= 15);
+
+    // Keep only a few records so most of the vector IDs are past the
+    // number of records. New records should still be linked into
+    // the graph left by the deletes.
+    for i in (101..500).step_by(2) {
+        collection.delete(&VectorID::from(i as usize)).unwrap();
+    }
+
+    let start = collection.slots.len();
+    let records = Record::many_random(DIMENSION, 100);
+    for record in records.iter() {
+        collection.insert(record).unwrap();
+    }
+
+    let found = records
+        .iter()
+        .enumerate()
+        .filter(|(i, record)| {
+            let result = collection.search(&record.vector, 1).unwrap();
+            result[0].id as usize == start + i
+        })
+        .count();
+
+    assert!(found >= 95, "{found} of 100");
+}
+
+#[test]
+fn compact_with_remap() {
+    let mut collection = create_collection();
+    let record = collection.get(&VectorID(99)).unwrap();
+    for i in 0..LEN / 2 {
+        collection.delete(&VectorID::from(i)).unwrap();
+    }
+
+    let mapping = collection.compact(true).unwrap();
+    assert_eq!(mapping.len(), LEN / 2);
+    assert_eq!(mapping[&VectorID(99)], VectorID::from(LEN / 2 - 1));
+    assert_eq!(collection.slots.len(), LEN / 2);
+    assert_eq!(collection.base_layer.len(), LEN / 2);
+
+    // The records are moved to the new vector IDs.
+    let new_id = mapping[&VectorID(99)];
+    assert_eq!(collection.get(&new_id).unwrap().data, record.data);
+    let result = collection.search(&record.vector, 1).unwrap();
+    assert_eq!(result[0].id, new_id.0);
+
+    // New records use the next slot after the compacted ones.
+    collection.insert(&Record::random(DIMENSION)).unwrap();
+    assert!(collection.contains(&VectorID::from(LEN / 2)));
+}
+
+#[test]
+fn compact_without_remap() {
+    let mut collection = create_collection();
+    for i in (0..LEN).filter(|i| i % 2 == 1) {
+        collection.delete(&VectorID::from(i)).unwrap();
+    }
+
+    let mapping = collection.compact(false).unwrap();
+    assert!(mapping.iter().all(|(old, new)| old == new));
+
+    // Only the deleted slot at the end is reclaimed.
+    assert_eq!(collection.slots.len(), LEN - 1);
+    assert_eq!(collection.len(), LEN / 2);
+
+    let record = collection.get(&VectorID(2)).unwrap();
+    let result = collection.search(&record.vector, 1).unwrap();
+    assert_eq!(result[0].id, 2);
+}
+
+#[test]
+fn compact_with_threshold() {
+    let config =
+        Config { compaction_threshold: Some(0.2), ..Default::default() };
+    let records = Record::many_random(DIMENSION, LEN);
+    let mut collection = Collection::build(&config, &records).unwrap();
+
+    // The collection compacts itself on the 20th deletion.
+    for i in (0..20).rev() {
+        let compacted = collection.delete(&VectorID::from(LEN - 20 + i));
+        assert_eq!(compacted.unwrap(), i == 0);
+    }
+
+    assert_eq!(collection.deleted, 0);
+    assert_eq!(collection.slots.len(), LEN - 20);
+}
+
 #[test]
 fn update() {
     let mut collection = create_collection();
@@ -112,7 +273,7 @@ fn search() {
     // The search is not always exact, so we check if
     // the distance is within the true distances.
     let distances: Vec<f32> = truth.par_iter().map(|i| i.distance).collect();
-    assert_eq!(distances.contains(&result[0].distance), true);
+    assert!(distances.contains(&result[0].distance));
 
     // Search results should be within the relevancy.
     let last_result = result.last().unwrap();
@@ -121,6 +282,450 @@ fn search() {
     assert!(last_truth.distance <= collection.relevancy);
 }
 
+#[test]
+fn search_with_params() {
+    let records = Record::many_random(DIMENSION, 1000);
+    let collection = Collection::build(&Config::default(), &records).unwrap();
+    let query = Vector::random(DIMENSION);
+
+    // Searching every node finds the true nearest neighbors.
+    let params = SearchParams::default().with_ef_search(1000).with_ef_upper(20);
+    let result = collection.search_with_params(&query, 5, &params).unwrap();
+    let truth = collection.true_search(&query, 5).unwrap();
+    let ids = |results: &[SearchResult]| -> Vec<u32> {
+        results.iter().map(|r| r.id).collect()
+    };
+
+    assert_eq!(ids(&result), ids(&truth));
+    assert!(result.iter().all(|r| r.vector.is_none()));
+
+    // The relevancy and vectors apply to the search only.
+    let vector = &records[0].vector;
+    let params = SearchParams::default().with_relevancy(0.0).with_vectors();
+    let result = collection.search_with_params(vector, 5, &params).unwrap();
+    assert_eq!(ids(&result), vec![0]);
+    assert_eq!(result[0].vector.as_ref(), Some(vector));
+
+    assert_eq!(collection.relevancy, -1.0);
+    assert_eq!(collection.config.ef_search, Config::default().ef_search);
+    assert_eq!(collection.search(vector, 5).unwrap().len(), 5);
+}
+
+#[test]
+fn search_with_params_selective_filter() {
+    let records = Record::many_random(DIMENSION, 1000);
+    let collection = Collection::build(&Config::default(), &records).unwrap();
+    let view = collection.view();
+
+    // The filter allows too few records so they're scanned instead
+    // and the results are shaped by the same parameters.
+    let filter = |id: &VectorID| id.0 == 999;
+    let vector = &records[999].vector;
+    let params = SearchParams::default().with_relevancy(0.0).with_vectors();
+    let result = view.search_layers(vector, 5, &params, &filter).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].vector.as_ref(), Some(vector));
+
+    let query = Vector::random(DIMENSION);
+    let result = view.search_layers(&query, 5, &params, &filter).unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn search_recall_per_distance() {
+    // The random values are centered so the signs are mixed.
+    // Jaccard uses sparse vectors with the negative values zeroed.
+    let random = |distance: &Distance| -> Vector {
+        let iter = Vector::random(DIMENSION).0.into_iter().map(|x| x - 0.5);
+        match distance {
+            Distance::Jaccard => iter.map(|x| x.max(0.0)).collect(),
+            _ => iter.collect(),
+        }
+    };
+
+    let distances = [
+        Distance::Dot,
+        Distance::Euclidean,
+        Distance::Cosine,
+        Distance::Hamming,
+        Distance::Manhattan,
+        Distance::Chebyshev,
+        Distance::SquaredEuclidean,
+        Distance::Jaccard,
+    ];
+
+    for distance in &distances {
+        let records: Vec<Record> = (0..1000)
+            .map(|_| Record::new(&random(distance), &0.into()))
+            .collect();
+
+        let distance = distance.clone();
+        let config = Config { distance, ef_search: 64, ..Default::default() };
+        let distance = &config.distance;
+        let collection = Collection::build(&config, &records).unwrap();
+
+        // Results tied with the furthest true neighbor count as
+        // found because the order of equal distances is arbitrary.
+        let (mut found, mut total) = (0, 0);
+        for _ in 0..20 {
+            let query = random(distance);
+            let result = collection.search(&query, 10).unwrap();
+            let truth = collection.true_search(&query, 10).unwrap();
+            let furthest = distance.to_distance(truth[9].distance);
+
+            let within = |r: &SearchResult| {
+                distance.to_distance(r.distance) <= furthest + 1e-4
+            };
+
+            found += result.iter().filter(|r| within(r)).count();
+            total += truth.len();
+        }
+
+        let recall = found as f32 / total as f32;
+        assert!(recall >= 0.9, "{distance:?} recall: {recall}");
+    }
+}
+
+#[test]
+fn insert_grows_layers() {
+    let mut collection = Collection::new(&Config::default());
+    let records = Record::many_random(DIMENSION, 1000);
+    for chunk in records.chunks(50) {
+        collection.insert_many(chunk).unwrap();
+    }
+
+    // About ml of the nodes are in the first upper layer.
+    let layers = &collection.upper_layers;
+    let count = |layer: &[UpperNode]| {
+        layer.iter().filter(|node| !node.is_empty()).count()
+    };
+
+    assert!(layers.len() >= 2);
+    let len = count(&layers[0]);
+    assert!((200..400).contains(&len), "first layer: {len}");
+
+    // The nodes of a layer are in the layers below and
+    // only link to the nodes of the same layer.
+    for (i, layer) in layers.iter().enumerate() {
+        for (id, node) in layer.iter().enumerate() {
+            if i > 0 && !node.is_empty() {
+                assert!(!layers[i - 1][id].is_empty());
+            }
+
+            for neighbor in node.iter().take_while(|id| id.is_valid()) {
+                assert!(!layer[neighbor.0 as usize].is_empty());
+            }
+        }
+    }
+
+    // The records are found by their own vectors.
+    let found = (0..50)
+        .filter(|i| {
+            let vector = &records[i * 20].vector;
+            let result = collection.search(vector, 1).unwrap();
+            result[0].id as usize == i * 20
+        })
+        .count();
+
+    assert!(found >= 48, "found: {found}");
+}
+
+#[test]
+fn entry_after_delete() {
+    let records = Record::many_random(DIMENSION, 1000);
+    let mut collection =
+        Collection::build(&Config::default(), &records).unwrap();
+    assert_eq!(collection.entry, VectorID(0));
+
+    // The entry is replaced by a live node in the highest layer
+    // that still has one when it's deleted.
+    for i in 0..900 {
+        collection.delete(&VectorID::from(i as usize)).unwrap();
+
+        let entry = collection.entry;
+        assert!(collection.contains(&entry));
+
+        let layers = &collection.upper_layers;
+        let level = level_of(layers, &entry);
+        let above = layers[level.0..].iter().flatten();
+        assert!(above.clone().all(|node| node.is_empty()));
+    }
+
+    let result = collection.search(&records[950].vector, 1).unwrap();
+    assert_eq!(result[0].id, 950);
+
+    // The entry is invalid without records and the first
+    // inserted record becomes the entry.
+    for i in 900..1000 {
+        collection.delete(&VectorID::from(i as usize)).unwrap();
+    }
+
+    assert!(!collection.entry.is_valid());
+    collection.insert(&records[0]).unwrap();
+    assert_eq!(collection.entry, VectorID(1000));
+}
+
+#[test]
+fn search_recall_clustered() {
+    // Tight clusters of records around random centers.
+    let centers: Vec<Vector> =
+        (0..50).map(|_| Vector::random(DIMENSION)).collect();
+    let around = |center: &Vector| -> Vector {
+        let noise = Vector::random(DIMENSION).0.into_iter();
+        center.0.iter().zip(noise).map(|(c, n)| c + (n - 0.5) * 0.05).collect()
+    };
+
+    let records: Vec<Record> = (0..2000)
+        .map(|i| Record::new(&around(&centers[i % 50]), &0.into()))
+        .collect();
+
+    for (extend, keep) in [(false, false), (true, true)] {
+        let config = Config {
+            extend_candidates: extend,
+            keep_pruned_connections: keep,
+            ..Default::default()
+        };
+
+        // Half of the records are linked by inserting them.
+        let mut collection =
+            Collection::build(&config, &records[..1000]).unwrap();
+        collection.insert_many(&records[1000..]).unwrap();
+
+        let (mut found, mut total) = (0, 0);
+        for center in centers.iter() {
+            let query = around(center);
+            let result = collection.search(&query, 10).unwrap();
+            let truth = collection.true_search(&query, 10).unwrap();
+            let ids: Vec<u32> = truth.iter().map(|r| r.id).collect();
+            found += result.iter().filter(|r| ids.contains(&r.id)).count();
+            total += truth.len();
+        }
+
+        let recall = found as f32 / total as f32;
+        assert!(recall >= 0.95, "{config:?} recall: {recall}");
+    }
+}
+
+#[test]
+fn select_neighbors_heuristic() {
+    // The node is at 0 and the candidates are at 1, 2, 3, and -1.
+    let config = Config::default();
+    let mut vectors = VectorStore::new(&config);
+    for (i, x) in [1.0, 2.0, 3.0, -1.0].into_iter().enumerate() {
+        vectors.insert(i.into(), &vec![x, 0.0].into());
+    }
+
+    let candidates =
+        [1.0, 2.0, 3.0, 1.0].into_iter().enumerate().map(|(i, d)| Candidate {
+            distance: OrderedFloat(d),
+            vector_id: i.into(),
+        });
+
+    // The candidates behind the nearest one are pruned.
+    let mut search = Search::new(4, config.distance);
+    let ids = |selected: Vec<Candidate>| -> Vec<u32> {
+        selected.iter().map(|c| c.vector_id.0).collect()
+    };
+
+    let selected = search.select_from(candidates.clone(), &vectors, 4, false);
+    assert_eq!(ids(selected), vec![0, 3]);
+
+    // The pruned candidates fill the free slots if they're kept.
+    let selected = search.select_from(candidates, &vectors, 4, true);
+    assert_eq!(ids(selected), vec![0, 3, 1, 2]);
+}
+
+/// Creates a base layer of nodes on a line where each node
+/// is linked to the nodes next to it.
+fn create_line(len: usize) -> (Vec<BaseNode>, VectorStore) {
+    let mut vectors = VectorStore::new(&Config::default());
+    let mut layer = vec![];
+    for i in 0..len {
+        vectors.insert(i.into(), &vec![i as f32, 0.0].into());
+        let neighbors = [i.wrapping_sub(1), i + 1];
+        let neighbors = neighbors.into_iter().filter(|n| *n < len);
+
+        let mut node = BaseNode::new(2);
+        node.allocate(neighbors.map(VectorID::from));
+        layer.push(node);
+    }
+
+    (layer, vectors)
+}
+
+#[test]
+fn unlink_reconnects_neighbors() {
+    let (mut layer, vectors) = create_line(10);
+    let config = Config::default();
+
+    // The neighbors of the deleted node are linked to each other
+    // and the nodes further away are left as they are.
+    let changed =
+        unlink(layer.as_mut_slice(), &[VectorID(5)], &vectors, &config);
+    let ids =
+        |node: &BaseNode| -> Vec<u32> { node.iter().map(|id| id.0).collect() };
+
+    assert_eq!(changed.len(), 2);
+    assert!(changed.contains(&VectorID(4)) && changed.contains(&VectorID(6)));
+    assert_eq!(ids(&layer[4]), vec![3, 6]);
+    assert_eq!(ids(&layer[6]), vec![7, 4]);
+    assert_eq!(ids(&layer[3]), vec![2, 4]);
+    assert!(layer[5].iter().all(|id| !id.is_valid()));
+}
+
+#[test]
+fn search_stops_at_furthest_nearest() {
+    let (layer, vectors) = create_line(10);
+    let query = vec![0.0, 0.0].into();
+
+    // The search walks from the entry to the query and stops once
+    // the next candidate is further than the nearest neighbors.
+    let mut search = Search::new(10, Distance::Euclidean);
+    search.ef = 2;
+    search.push(&VectorID(5), &query, &vectors);
+    search.search(layer.as_slice(), &query, &vectors);
+
+    let ids: Vec<u32> = search.iter().map(|c| c.vector_id.0).collect();
+    assert_eq!(ids, vec![0, 1]);
+    assert!(!search.limited);
+
+    let unvisited: Vec<u32> =
+        (0..10).filter(|i| search.visited.insert(&VectorID(*i))).collect();
+    assert_eq!(unvisited, vec![7, 8, 9]);
+}
+
+#[test]
+fn search_filtered_visit_limit() {
+    let (layer, vectors) = create_line(10);
+    let query = vec![0.0, 0.0].into();
+    let filter = |id: &VectorID| id.0 == 9;
+
+    // Without a limit, a selective filter traverses the whole graph.
+    let mut search = Search::new(10, Distance::Euclidean);
+    search.ef = 2;
+    search.push_filtered(&VectorID(5), &query, &vectors, &filter);
+    search.search_filtered(layer.as_slice(), &query, &vectors, &filter);
+    let ids: Vec<u32> = search.iter().map(|c| c.vector_id.0).collect();
+    assert_eq!(ids, vec![9]);
+    assert!(!search.limited);
+
+    // The search stops at the limit while the list isn't full.
+    let mut search = Search::new(10, Distance::Euclidean);
+    search.ef = 2;
+    search.visit_limit = 3;
+    search.push_filtered(&VectorID(5), &query, &vectors, &filter);
+    search.search_filtered(layer.as_slice(), &query, &vectors, &filter);
+    assert!(search.limited);
+    assert_eq!(search.iter().count(), 0);
+    assert!(search.visited.insert(&VectorID(9)));
+}
+
+#[test]
+fn search_with_selective_filter() {
+    let len = 1000;
+    let records: Vec<Record> = (0..len)
+        .map(|i| {
+            let vector = Vector::random(DIMENSION);
+            let data = HashMap::from([("tenant", i % 200)]);
+            Record::new(&vector, &data.into())
+        })
+        .collect();
+
+    let config = Config::default();
+    let collection = Collection::build(&config, &records).unwrap();
+
+    // Only 5 records match so the search scans them
+    // after visiting a part of the graph.
+    let query = Vector::random(DIMENSION);
+    let filter = crate::filter::Filter::eq("tenant", 7);
+    let result = collection.search_with_filter(&query, 10, &filter).unwrap();
+
+    let mut truth = collection.true_search(&query, len).unwrap();
+    truth.retain(|r| filter.matches(&r.data));
+    let ids: Vec<u32> = result.iter().map(|r| r.id).collect();
+    let true_ids: Vec<u32> = truth.iter().map(|r| r.id).collect();
+    assert_eq!(ids, true_ids);
+}
+
+#[test]
+fn true_search_with_similarity() {
+    let records = Record::many_random(DIMENSION, LEN);
+    let query = Vector::random(DIMENSION);
+
+    for distance in [Distance::Dot, Distance::Cosine] {
+        let config =
+            Config { distance: distance.clone(), ..Default::default() };
+        let mut collection = Collection::build(&config, &records).unwrap();
+
+        // The most similar record has the highest score.
+        let scores: Vec<f32> = records
+            .iter()
+            .map(|record| distance.calculate(&query, &record.vector))
+            .collect();
+        let best = scores.iter().copied().fold(f32::MIN, f32::max);
+
+        let result = collection.true_search(&query, 10).unwrap();
+        assert!((result[0].distance - best).abs() <= 1e-4);
+        assert!(result.windows(2).all(|w| w[0].distance >= w[1].distance));
+
+        // The relevancy is the min score of the results.
+        collection.relevancy = result[4].distance;
+        let relevant = collection.true_search(&query, 10).unwrap();
+        assert_eq!(relevant.len(), 5);
+        assert!(relevant.iter().all(|r| r.distance >= collection.relevancy));
+    }
+}
+
+#[test]
+fn true_search_with_metric() {
+    let records = Record::many_random(DIMENSION, LEN);
+    let config = Config { distance: Distance::Cosine, ..Default::default() };
+    let collection = Collection::build(&config, &records).unwrap();
+
+    // The custom function compares the vectors as inserted.
+    let manhattan = |a: &[f32], b: &[f32]| -> f32 {
+        a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).sum()
+    };
+
+    let query = Vector::random(DIMENSION);
+    let result = collection.true_search_with(&query, 5, &manhattan).unwrap();
+
+    let mut expected: Vec<f32> = records
+        .iter()
+        .map(|record| Distance::Manhattan.calculate(&query, &record.vector))
+        .collect();
+    expected.sort_by(|a, b| a.total_cmp(b));
+
+    assert_eq!(result.len(), 5);
+    for (result, expected) in result.iter().zip(expected.iter()) {
+        assert!((result.distance - expected).abs() <= 1e-3);
+    }
+}
+
+#[test]
+fn search_with_filter() {
+    let len = 1000;
+    let config = Config::default();
+
+    // Assign each record to one of the three tenants.
+    let records: Vec<Record> = (0..len)
+        .map(|i| {
+            let vector = Vector::random(DIMENSION);
+            let data = HashMap::from([("tenant", i % 3)]);
+            Record::new(&vector, &data.into())
+        })
+        .collect();
+
+    let collection = Collection::build(&config, &records).unwrap();
+
+    let query = Vector::random(DIMENSION);
+    let filter = crate::filter::Filter::eq("tenant", 1);
+    let result = collection.search_with_filter(&query, 5, &filter).unwrap();
+
+    assert_eq!(result.len(), 5);
+    assert!(result.iter().all(|r| filter.matches(&r.data)));
+}
+
 #[test]
 fn get() {
     let records = Record::many_random(DIMENSION, LEN);
@@ -136,6 +741,31 @@ fn get() {
     assert_eq!(record.vector, records[index].vector);
 }
 
+#[test]
+fn get_with_cosine() {
+    let records = Record::many_random(DIMENSION, LEN);
+    let config = Config { distance: Distance::Cosine, ..Default::default() };
+    let collection = Collection::build(&config, &records).unwrap();
+
+    // The stored vectors are normalized.
+    let id = VectorID(5);
+    let stored = &collection[&id];
+    let norm = stored.0.iter().map(|x| x * x).sum::<f32>().sqrt();
+    assert!((norm - 1.0).abs() <= 1e-5);
+
+    // The original vector is restored from its norm.
+    let vector = collection.get(&id).unwrap().vector;
+    let diff = vector.0.iter().zip(records[5].vector.0.iter());
+    assert!(diff.map(|(a, b)| (a - b).abs()).all(|d| d <= 1e-5));
+
+    // The similarity is the cosine of the original vectors.
+    let query = Vector::random(DIMENSION);
+    let result = collection.true_search(&query, 1).unwrap();
+    let vector = &records[result[0].id as usize].vector;
+    let expected = Distance::Cosine.calculate(&query, vector);
+    assert!((result[0].distance - expected).abs() <= 1e-5);
+}
+
 #[test]
 fn list() {
     let collection = create_collection();
@@ -148,7 +778,18 @@ fn list() {
 fn config_with_distance() {
     let ef = 10;
     let ml = 1.0;
-    for dist in vec!["cosine", "dot", "euclidean"] {
+    let distances = [
+        "cosine",
+        "dot",
+        "euclidean",
+        "hamming",
+        "manhattan",
+        "chebyshev",
+        "squared_euclidean",
+        "jaccard",
+    ];
+
+    for dist in distances {
         Config::new(ef, ef, ml, dist).unwrap();
     }
 }
@@ -158,7 +799,7 @@ fn config_with_distance() {
 fn config_with_distance_panic() {
     let ef = 10;
     let ml = 1.0;
-    for dist in vec!["l2", "test"] {
+    for dist in ["l2", "test"] {
         Config::new(ef, ef, ml, dist).unwrap();
     }
 }
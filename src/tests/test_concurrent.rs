@@ -0,0 +1,153 @@
+use super::*;
+use std::thread;
+
+#[test]
+fn insert_while_searching() {
+    let collection = ConcurrentCollection::from(create_collection());
+
+    thread::scope(|scope| {
+        for _ in 0..2 {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let record = Record::random(DIMENSION);
+                    collection.insert(&record).unwrap();
+                }
+            });
+        }
+
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    let query = Vector::random(DIMENSION);
+                    let result = collection.search(&query, 5).unwrap();
+                    assert_eq!(result.len(), 5);
+                }
+            });
+        }
+    });
+
+    assert_eq!(collection.len(), LEN + 20);
+}
+
+#[test]
+fn delete_while_searching() {
+    let collection = ConcurrentCollection::from(create_collection());
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            for i in 10..LEN {
+                collection.delete(&i.into()).unwrap();
+            }
+        });
+
+        scope.spawn(|| {
+            for _ in 0..20 {
+                let query = Vector::random(DIMENSION);
+                collection.search(&query, 5).unwrap();
+            }
+        });
+    });
+
+    // Deleted records must never be returned.
+    let query = Vector::random(DIMENSION);
+    let result = collection.search(&query, 10).unwrap();
+    assert!(result.iter().all(|r| r.id < 10));
+    assert_eq!(collection.len(), 10);
+}
+
+#[test]
+fn update_while_searching() {
+    let collection = ConcurrentCollection::from(create_collection());
+    let records = Record::many_random(DIMENSION, 10);
+
+    thread::scope(|scope| {
+        // Both threads update the same records.
+        for _ in 0..2 {
+            scope.spawn(|| {
+                for (i, record) in records.iter().enumerate() {
+                    collection.update(&i.into(), record).unwrap();
+                }
+            });
+        }
+
+        for _ in 0..2 {
+            scope.spawn(|| {
+                for _ in 0..20 {
+                    let query = Vector::random(DIMENSION);
+                    let result = collection.search(&query, 5).unwrap();
+                    assert_eq!(result.len(), 5);
+                }
+            });
+        }
+    });
+
+    // The updated records are found by their new vectors.
+    for (i, record) in records.iter().enumerate() {
+        let result = collection.search(&record.vector, 1).unwrap();
+        assert_eq!(result[0].id, i as u32);
+    }
+
+    assert_eq!(collection.len(), LEN);
+}
+
+#[test]
+fn update_and_snapshot() {
+    let collection = ConcurrentCollection::from(create_collection());
+
+    let record = Record::random(DIMENSION);
+    collection.update(&VectorID(5), &record).unwrap();
+    assert_eq!(collection.get(&VectorID(5)).unwrap().data, record.data);
+
+    // The snapshot is a regular collection with the same records.
+    let snapshot = collection.snapshot();
+    assert_eq!(snapshot.len(), LEN);
+    assert_eq!(snapshot.get(&VectorID(5)).unwrap().data, record.data);
+
+    let result = snapshot.search(&record.vector, 1).unwrap();
+    assert_eq!(result[0].id, 5);
+}
+
+#[test]
+fn insert_grows_layers() {
+    let collection = ConcurrentCollection::new(&Config::default());
+    let records = Record::many_random(DIMENSION, 200);
+    for record in records.iter() {
+        collection.insert(record).unwrap();
+    }
+
+    // The inserted records are promoted to the upper layers.
+    let snapshot = collection.snapshot();
+    assert!(!snapshot.upper_layers.is_empty());
+
+    let result = collection.search(&records[100].vector, 1).unwrap();
+    assert_eq!(result[0].id, 100);
+}
+
+#[test]
+fn delete_entry() {
+    let records = Record::many_random(DIMENSION, LEN);
+    let collection = Collection::build(&Config::default(), &records).unwrap();
+    let collection = ConcurrentCollection::from(collection);
+
+    // The searches start from another node once the entry is deleted.
+    collection.delete(&VectorID(0)).unwrap();
+    let entry = collection.snapshot().entry;
+    assert!(entry.is_valid() && entry != VectorID(0));
+
+    let result = collection.search(&records[1].vector, 1).unwrap();
+    assert_eq!(result[0].id, 1);
+}
+
+#[test]
+fn search_with_params() {
+    let records = Record::many_random(DIMENSION, LEN);
+    let collection = Collection::build(&Config::default(), &records).unwrap();
+    let collection = ConcurrentCollection::from(collection);
+
+    let vector = &records[0].vector;
+    let params = SearchParams::default().with_relevancy(0.0).with_vectors();
+    let result = collection.search_with_params(vector, 5, &params).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].vector.as_ref(), Some(vector));
+    assert_eq!(collection.search(vector, 5).unwrap().len(), 5);
+}
@@ -1,4 +1,6 @@
 use super::*;
+use crate::func::utils::UpperNode;
+use std::collections::HashSet;
 
 #[test]
 fn new() {
@@ -8,12 +10,93 @@ fn new() {
 
 #[test]
 fn new_with_distance() {
-    let mut config = Config::default();
-    config.distance = Distance::Cosine;
+    let config = Config { distance: Distance::Cosine, ..Default::default() };
     let mut collection = Collection::new(&config);
     collection.insert(&Record::random(DIMENSION)).unwrap();
 }
 
+#[test]
+fn save_collection_with_cosine() {
+    let mut db = Database::new("data/015").unwrap();
+    let records = Record::many_random(DIMENSION, LEN);
+    let config = Config { distance: Distance::Cosine, ..Default::default() };
+    let collection = Collection::build(&config, &records).unwrap();
+    db.save_collection(NAME, &collection).unwrap();
+
+    // The norms are saved to restore the original vectors.
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.vectors.norms, collection.vectors.norms);
+    let id = VectorID(0);
+    assert_eq!(
+        saved.get(&id).unwrap().vector,
+        collection.get(&id).unwrap().vector
+    );
+}
+
+#[test]
+fn save_collection_with_custom_distance() {
+    let mut db = Database::new("data/016").unwrap();
+    let metric = |a: &[f32], b: &[f32]| -> f32 {
+        a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).sum()
+    };
+
+    // The function is saved by name and resolved when loaded.
+    let distance = Distance::register("saved_l1", metric).unwrap();
+    let config = Config { distance: distance.clone(), ..Default::default() };
+    let records = Record::many_random(DIMENSION, LEN);
+    let collection = Collection::build(&config, &records).unwrap();
+    db.save_collection(NAME, &collection).unwrap();
+
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.config.distance, distance);
+
+    let query = Vector::random(DIMENSION);
+    let ids = |c: &Collection| -> Vec<u32> {
+        c.search(&query, 10).unwrap().iter().map(|r| r.id).collect()
+    };
+
+    assert_eq!(ids(&saved), ids(&collection));
+}
+
+#[test]
+fn save_collection_with_sparse() {
+    let db = create_test_database("data/017");
+    let handle = db.collection(NAME).unwrap();
+
+    let sparse = SparseVector::new(vec![7, 3], vec![0.7, 0.3]).unwrap();
+    let record = Record::random(DIMENSION).with_sparse(sparse.clone());
+    handle.insert(&record).unwrap();
+    let id = VectorID::from(LEN);
+
+    // The inverted index is rebuilt from the saved sparse vectors.
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.get(&id).unwrap().sparse, Some(sparse.clone()));
+    assert_eq!(saved.sparse_search(&sparse, 5).unwrap()[0].id, id.0);
+
+    handle.delete(&id).unwrap();
+    let saved = db.get_collection(NAME).unwrap();
+    assert!(saved.sparse_search(&sparse, 5).unwrap().is_empty());
+}
+
+#[test]
+fn save_collection_with_custom_m() {
+    let mut db = Database::new("data/018").unwrap();
+    let config = Config { m: 8, m0: 12, ..Default::default() };
+    let records = Record::many_random(DIMENSION, LEN);
+    let collection = Collection::build(&config, &records).unwrap();
+    db.save_collection(NAME, &collection).unwrap();
+
+    // The nodes are restored with the saved number of neighbors.
+    let mut saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.config.m, 8);
+    assert_eq!(saved.config.m0, 12);
+    assert_eq!(saved.base_layer, collection.base_layer);
+    assert_eq!(saved.upper_layers, collection.upper_layers);
+
+    saved.insert(&Record::random(DIMENSION)).unwrap();
+    assert!(saved.base_layer.iter().all(|node| node.0.len() == 12));
+}
+
 #[test]
 fn get_collection() {
     let db = create_test_database("data/002");
@@ -57,3 +140,230 @@ fn delete_collection() {
     db.delete_collection(NAME).unwrap();
     assert_eq!(db.len(), 0);
 }
+
+#[test]
+fn save_collection_incremental() {
+    let mut db = create_test_database("data/006");
+    let mut collection = db.get_collection(NAME).unwrap();
+
+    // Modify the loaded collection and save only the changes.
+    let record = Record::random(DIMENSION);
+    collection.insert(&record).unwrap();
+    collection.update(&VectorID(1), &record).unwrap();
+    collection.delete(&VectorID(0)).unwrap();
+    db.save_collection(NAME, &collection).unwrap();
+
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.len(), LEN);
+    assert!(!saved.contains(&VectorID(0)));
+    assert_eq!(saved.get(&VectorID(1)).unwrap().data, record.data);
+    assert_eq!(saved.get(&VectorID::from(LEN)).unwrap().data, record.data);
+
+    // The saved index graph should match the original.
+    assert_eq!(saved.base_layer, collection.base_layer);
+    assert_eq!(saved.upper_layers, collection.upper_layers);
+    assert_eq!(saved.entry, collection.entry);
+
+    let query = Vector::random(DIMENSION);
+    let ids = |c: &Collection| -> Vec<u32> {
+        c.search(&query, 10).unwrap().iter().map(|r| r.id).collect()
+    };
+
+    assert_eq!(ids(&saved), ids(&collection));
+}
+
+#[test]
+fn get_collection_unsupported_format() {
+    let path = "data/022";
+    let mut db = create_test_database(path);
+    drop(db);
+
+    // Store the collection the way it was stored before
+    // the storage format was versioned.
+    let collection = create_collection();
+    let sled = sled::open(path).unwrap();
+    sled.insert(NAME, bincode::serialize(&collection).unwrap()).unwrap();
+    drop(sled);
+
+    db = Database::open(path).unwrap();
+    let err = db.get_collection(NAME).unwrap_err();
+    assert_eq!(err.message(), Error::unsupported_format().message());
+
+    // The collection can be saved again in the current format.
+    db.save_collection(NAME, &collection).unwrap();
+    assert_eq!(db.get_collection(NAME).unwrap().len(), LEN);
+}
+
+#[test]
+fn save_collection_with_deleted() {
+    let mut db = Database::new("data/021").unwrap();
+    let config =
+        Config { compaction_threshold: Some(0.1), ..Config::default() };
+    let records = Record::many_random(DIMENSION, LEN);
+    let mut collection = Collection::build(&config, &records).unwrap();
+
+    for i in 0..5 {
+        assert!(!collection.delete(&VectorID(i)).unwrap());
+    }
+
+    // The deleted records count towards the threshold after loading.
+    db.save_collection(NAME, &collection).unwrap();
+    let mut collection = db.get_collection(NAME).unwrap();
+    for i in 5..9 {
+        assert!(!collection.delete(&VectorID(i)).unwrap());
+    }
+
+    assert!(collection.delete(&VectorID(9)).unwrap());
+    assert_eq!(collection.deleted, 0);
+}
+
+#[test]
+fn insert_tracks_linked_nodes() {
+    let db = create_test_database("data/019");
+    let mut collection = db.get_collection(NAME).unwrap();
+    let before = collection.clone();
+    collection.insert(&Record::random(DIMENSION)).unwrap();
+
+    // Only the new node and the nodes linked to it are tracked.
+    let id = VectorID::from(LEN);
+    let mut linked: HashSet<VectorID> = HashSet::from([id]);
+    linked.extend(collection.base_layer[LEN].iter().copied());
+    for layer in collection.upper_layers.iter() {
+        let node = layer.get(LEN).map(|node| node.iter());
+        linked.extend(node.into_iter().flatten().copied());
+    }
+
+    let nodes = collection.changes.lock().nodes.clone();
+    assert!(nodes.is_subset(&linked));
+
+    // Every node whose neighbors changed is tracked. The layers
+    // grow with empty nodes which are the same as missing nodes.
+    let node = |layer: &[UpperNode], i: usize| -> UpperNode {
+        layer.get(i).cloned().unwrap_or_default()
+    };
+
+    for i in 0..LEN {
+        let changed = before.base_layer[i] != collection.base_layer[i]
+            || (before.upper_layers.iter().zip(&collection.upper_layers))
+                .any(|(old, new)| node(old, i) != node(new, i));
+        assert!(!changed || nodes.contains(&VectorID::from(i)), "{i}");
+    }
+}
+
+#[test]
+fn save_collection_copy() {
+    let mut db = create_test_database("data/007");
+    let mut collection = db.get_collection(NAME).unwrap();
+    collection.delete(&VectorID(0)).unwrap();
+
+    // Saving under a new name writes the whole collection.
+    db.save_collection("copy", &collection).unwrap();
+    let copy = db.get_collection("copy").unwrap();
+    assert_eq!(copy.len(), LEN - 1);
+    assert_eq!(db.len(), 2);
+
+    // The original collection is unchanged.
+    let original = db.get_collection(NAME).unwrap();
+    assert_eq!(original.len(), LEN);
+}
+
+#[test]
+fn save_collection_stale() {
+    let mut db = create_test_database("data/008");
+    let mut first = db.get_collection(NAME).unwrap();
+    let mut second = db.get_collection(NAME).unwrap();
+
+    first.insert(&Record::random(DIMENSION)).unwrap();
+    db.save_collection(NAME, &first).unwrap();
+
+    // The second copy is outdated so it's written as a whole.
+    second.delete(&VectorID(0)).unwrap();
+    db.save_collection(NAME, &second).unwrap();
+
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.len(), LEN - 1);
+    assert!(!saved.contains(&VectorID::from(LEN)));
+}
+
+#[test]
+fn collection_handle() {
+    let mut db = create_test_database("data/009");
+    let handle = db.collection(NAME).unwrap();
+
+    // Changes through the handle are saved without save_collection.
+    let record = Record::random(DIMENSION);
+    let records = vec![record.clone(); 2];
+    let id = handle.insert_many(&records).unwrap()[0];
+    handle.delete(&VectorID(0)).unwrap();
+
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.len(), LEN + 1);
+    assert!(!saved.contains(&VectorID(0)));
+    assert_eq!(saved.get(&id).unwrap().data, record.data);
+
+    // Handles of a deleted collection can't write anymore.
+    db.delete_collection(NAME).unwrap();
+    assert!(handle.insert(&record).is_err());
+    assert!(db.get_collection(NAME).is_err());
+}
+
+#[test]
+fn collection_handle_threads() {
+    let db = create_test_database("data/010");
+    let handle = db.collection(NAME).unwrap();
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            std::thread::spawn(move || {
+                for _ in 0..5 {
+                    handle.insert(&Record::random(DIMENSION)).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // All handles of the collection share the same view.
+    let other = db.collection(NAME).unwrap();
+    assert_eq!(other.len(), LEN + 20);
+    assert_eq!(db.get_collection(NAME).unwrap().len(), LEN + 20);
+}
+
+#[test]
+fn collection_handle_compact() {
+    let db = create_test_database("data/011");
+    let handle = db.collection(NAME).unwrap();
+    handle.delete(&VectorID(0)).unwrap();
+
+    // The compacted collection is saved with the new vector IDs.
+    let mapping = handle.compact(true).unwrap();
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.len(), LEN - 1);
+    assert_eq!(saved.slots.len(), LEN - 1);
+    assert!(saved.contains(&mapping[&VectorID(1)]));
+}
+
+#[test]
+fn collection_handle_failed_write() {
+    let db = create_test_database("data/020");
+    let handle = db.collection(NAME).unwrap();
+    let record = Record::random(DIMENSION);
+
+    // The changes of a failed write are discarded.
+    let result = handle.write(|collection| {
+        collection.insert(&record)?;
+        Err::<(), Error>("The write failed.".into())
+    });
+
+    assert!(result.is_err());
+    assert_eq!(handle.len(), LEN);
+    assert_eq!(db.get_collection(NAME).unwrap().len(), LEN);
+
+    // The next write only saves its own changes.
+    handle.insert(&record).unwrap();
+    assert_eq!(db.get_collection(NAME).unwrap().len(), LEN + 1);
+}
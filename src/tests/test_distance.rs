@@ -13,3 +13,127 @@ fn distance_calculation() {
     assert_eq!(euclidean, 1.7320508);
     assert_eq!(cosine, 0.99385864);
 }
+
+#[test]
+fn hamming_distance() {
+    let a = Vector::from(vec![1.0, -3.0, 5.0, -0.5]);
+    let b = Vector::from(vec![2.0, 4.0, -6.0, -1.0]);
+
+    // Only the signs of the values are compared.
+    assert_eq!(Distance::Hamming.calculate(&a, &b), 2.0);
+    assert_eq!(Distance::Hamming.calculate(&a, &a), 0.0);
+
+    let values = b.0.iter().copied();
+    assert_eq!(Distance::Hamming.calculate_iter(&a, values), 2.0);
+    assert_eq!(Distance::from("hamming").unwrap(), Distance::Hamming);
+}
+
+#[test]
+fn similarity_to_distance() {
+    // Higher similarity scores are closer.
+    let dot = Distance::Dot;
+    assert!(dot.to_distance(0.9) < dot.to_distance(0.1));
+    assert_eq!(dot.from_distance(dot.to_distance(0.9)), 0.9);
+
+    let euclidean = Distance::Euclidean;
+    assert!(euclidean.to_distance(0.1) < euclidean.to_distance(0.9));
+    assert_eq!(euclidean.to_distance(0.9), 0.9);
+}
+
+#[test]
+fn additional_distances() {
+    let a = Vector::from(vec![1.0, 3.0, 5.0]);
+    let b = Vector::from(vec![2.0, 5.0, 2.0]);
+
+    assert_eq!(Distance::Manhattan.calculate(&a, &b), 6.0);
+    assert_eq!(Distance::Chebyshev.calculate(&a, &b), 3.0);
+    assert_eq!(Distance::SquaredEuclidean.calculate(&a, &b), 14.0);
+
+    // The weighted Jaccard distance is 1 - sum(min) / sum(max).
+    assert_eq!(Distance::Jaccard.calculate(&a, &b), 1.0 - 6.0 / 12.0);
+
+    // For binary vectors, it's the Jaccard distance of the sets.
+    let a = Vector::from(vec![1.0, 1.0, 0.0, 1.0]);
+    let b = Vector::from(vec![1.0, 0.0, 1.0, 1.0]);
+    assert_eq!(Distance::Jaccard.calculate(&a, &b), 0.5);
+
+    let zero = Vector::from(vec![0.0; 4]);
+    assert_eq!(Distance::Jaccard.calculate(&zero, &zero), 0.0);
+}
+
+#[test]
+fn register_custom_distance() {
+    // Weighs the first half of the dimensions twice as much.
+    let weighted = |a: &[f32], b: &[f32]| -> f32 {
+        let zip = a.iter().zip(b.iter()).enumerate();
+        let half = a.len() / 2;
+        zip.map(|(i, (x, y))| (x - y).abs() * if i < half { 2.0 } else { 1.0 })
+            .sum()
+    };
+
+    let distance = Distance::register("weighted", weighted).unwrap();
+    assert_eq!(Distance::from("weighted").unwrap(), distance);
+    assert!(!distance.is_similarity());
+
+    let a = Vector::from(vec![1.0, 3.0]);
+    let b = Vector::from(vec![2.0, 5.0]);
+    assert_eq!(distance.calculate(&a, &b), 4.0);
+    assert_eq!(distance.calculate_iter(&a, b.0.iter().copied()), 4.0);
+
+    // The built-in names can't be replaced.
+    assert!(Distance::register("euclidean", weighted).is_err());
+}
+
+#[test]
+fn deserialize_unregistered_distance() {
+    let metric = |a: &[f32], b: &[f32]| -> f32 {
+        a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    };
+
+    let distance = Distance::register("registered", metric).unwrap();
+    let bytes = bincode::serialize(&distance).unwrap();
+    let loaded: Distance = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(loaded, distance);
+
+    // The function is resolved by name when it's loaded.
+    let name = b"registered";
+    let index = bytes.windows(name.len()).position(|w| w == name).unwrap();
+    let mut bytes = bytes.clone();
+    bytes[index..index + name.len()].copy_from_slice(b"unknown123");
+    assert!(bincode::deserialize::<Distance>(&bytes).is_err());
+}
+
+#[test]
+fn register_distance_again() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Tracked(f32);
+
+    impl Metric for Tracked {
+        fn calculate(&self, _: &[f32], _: &[f32]) -> f32 {
+            self.0
+        }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let a = Vector::from(vec![1.0, 2.0]);
+    let first = Distance::register("tracked", Tracked(1.0)).unwrap();
+    let second = Distance::register("tracked", Tracked(2.0)).unwrap();
+
+    // The previous function is kept while it's still used.
+    assert_eq!(first.calculate(&a, &a), 1.0);
+    assert_eq!(second.calculate(&a, &a), 2.0);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 0);
+
+    // It's released once nothing uses it anymore.
+    drop(first);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+    let loaded = Distance::from("tracked").unwrap();
+    assert_eq!(loaded.calculate(&a, &a), 2.0);
+}
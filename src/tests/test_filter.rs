@@ -0,0 +1,132 @@
+use super::*;
+use crate::filter::Filter;
+
+fn create_metadata() -> Metadata {
+    let author = HashMap::from([("name", "Alice")]);
+    let tags: Vec<Metadata> = vec!["rust".into(), 2.into()];
+    let data = HashMap::from([
+        ("author", author.into()),
+        ("year", 2021.into()),
+        ("score", 0.5.into()),
+        ("tags", Metadata::Array(tags)),
+    ]);
+
+    Metadata::from(data)
+}
+
+#[test]
+fn filter_eq_nested() {
+    let data = create_metadata();
+    assert!(Filter::eq("author.name", "Alice").matches(&data));
+    assert!(!Filter::eq("author.name", "Bob").matches(&data));
+    assert!(!Filter::eq("author.email", "Alice").matches(&data));
+}
+
+#[test]
+fn filter_eq_numeric() {
+    let data = create_metadata();
+    assert!(Filter::eq("year", 2021.0).matches(&data));
+    assert!(Filter::eq("score", 0.5).matches(&data));
+}
+
+#[test]
+fn filter_range() {
+    let data = create_metadata();
+    assert!(Filter::range("year", Some(2000.0), None).matches(&data));
+    assert!(Filter::range("score", Some(0.5), Some(1.0)).matches(&data));
+    assert!(!Filter::range("year", None, Some(2020.0)).matches(&data));
+    assert!(!Filter::range("author", None, None).matches(&data));
+}
+
+#[test]
+fn filter_contains() {
+    let data = create_metadata();
+    assert!(Filter::contains("tags", "rust").matches(&data));
+    assert!(Filter::contains("tags", 2).matches(&data));
+    assert!(!Filter::contains("tags", "python").matches(&data));
+    assert!(!Filter::contains("year", 2021).matches(&data));
+}
+
+#[test]
+fn filter_logical_operators() {
+    let data = create_metadata();
+    let name = Filter::eq("author.name", "Alice");
+    let year = Filter::Gt("year".into(), 2021.into());
+
+    let and = Filter::And(vec![name.clone(), year.clone()]);
+    let or = Filter::Or(vec![name.clone(), year.clone()]);
+    let not = Filter::Not(Box::new(year));
+
+    assert!(!and.matches(&data));
+    assert!(or.matches(&data));
+    assert!(not.matches(&data));
+}
+
+#[test]
+fn filter_in_and_exists() {
+    let data = create_metadata();
+    let years = vec![2020.into(), 2021.into()];
+    assert!(Filter::In("year".into(), years).matches(&data));
+    assert!(Filter::exists("author.name").matches(&data));
+    assert!(!Filter::exists("author.email").matches(&data));
+}
+
+#[test]
+fn filter_parse() {
+    let expression = r#"author.name == "Alice" and not year < 2000"#;
+    let filter = Filter::parse(expression).unwrap();
+
+    let name = Filter::eq("author.name", "Alice");
+    let year = Filter::Lt("year".into(), 2000.into());
+    let expected = Filter::And(vec![name, Filter::Not(Box::new(year))]);
+    assert_eq!(filter, expected);
+}
+
+#[test]
+fn filter_parse_matches() {
+    let data = create_metadata();
+    let expressions = [
+        "author.name != 'Bob'",
+        "year >= 2021 and score <= 0.5",
+        "(year > 2030 or tags contains 'rust') and exists author",
+        "year in [2019, 2020, 2021]",
+        "author.name contains 'lic'",
+        "author.name >= 'Alice' and author.name <= 'Alice'",
+    ];
+
+    for expression in expressions {
+        let filter: Filter = expression.parse().unwrap();
+        assert!(filter.matches(&data), "{expression}");
+    }
+}
+
+#[test]
+fn filter_compare_inclusive() {
+    let data = create_metadata();
+    let name = |operator: &str, value: &str| {
+        let expression = format!("author.name {operator} '{value}'");
+        Filter::parse(&expression).unwrap().matches(&data)
+    };
+
+    // Text values compare the same way for all four operators.
+    assert!(name(">", "Aa") && name(">=", "Aa") && name(">=", "Alice"));
+    assert!(!name(">", "Alice") && !name(">=", "Bob"));
+    assert!(name("<", "Bob") && name("<=", "Bob") && name("<=", "Alice"));
+    assert!(!name("<", "Alice") && !name("<=", "Aa"));
+}
+
+#[test]
+fn filter_parse_invalid() {
+    let expressions = ["year >", "year ~ 1", "(year == 1", "tags in 'rust'"];
+    for expression in expressions {
+        assert!(Filter::parse(expression).is_err(), "{expression}");
+    }
+}
+
+#[test]
+fn filter_serialize() {
+    let filter = Filter::parse("year > 2000 or tags contains 'rust'").unwrap();
+    let bytes = bincode::serialize(&filter).unwrap();
+    let decoded: Filter = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(filter, decoded);
+}
@@ -0,0 +1,118 @@
+use super::*;
+use crate::filter::Filter;
+
+fn create_indexed_collection() -> Collection {
+    // Assign each record to one of the hundred tenants.
+    let records: Vec<Record> = (0..1000)
+        .map(|i| {
+            let tenant = format!("tenant-{}", i % 100);
+            let data = HashMap::from([("tenant", tenant)]);
+            Record::new(&Vector::random(DIMENSION), &data.into())
+        })
+        .collect();
+
+    let config = Config::default();
+    let mut collection = Collection::build(&config, &records).unwrap();
+    collection.create_index("tenant", IndexType::Keyword);
+    collection
+}
+
+/// Returns the sorted IDs from the index lookup.
+fn lookup(index: &FieldIndex, filter: Filter) -> Vec<u32> {
+    let ids = index.lookup(&filter).unwrap();
+    let mut ids: Vec<u32> = ids.iter().map(|id| id.0).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn index_lookup() {
+    let mut index = FieldIndex::new(IndexType::Integer);
+    let values: Vec<Metadata> =
+        vec![1.into(), 5.into(), 2.5.into(), vec![7, 9].into()];
+
+    for (i, value) in values.iter().enumerate() {
+        index.insert(&i.into(), Some(value));
+    }
+
+    // Non-integer values are always included in the lookups.
+    assert_eq!(lookup(&index, Filter::eq("", 5)), vec![1, 2]);
+    assert_eq!(
+        lookup(&index, Filter::range("", Some(2.0), Some(7.0))),
+        vec![1, 2, 3]
+    );
+    assert_eq!(lookup(&index, Filter::Gt("".into(), 5.into())), vec![2, 3]);
+    assert_eq!(lookup(&index, Filter::Lt("".into(), 1.into())), vec![2]);
+    assert_eq!(lookup(&index, Filter::Gte("".into(), 5.into())), vec![1, 2, 3]);
+    assert_eq!(lookup(&index, Filter::Lte("".into(), 1.into())), vec![0, 2]);
+    assert_eq!(lookup(&index, Filter::contains("", 9)), vec![2, 3]);
+    assert!(index.lookup(&Filter::exists("")).is_none());
+
+    index.remove(&VectorID(1), Some(&values[1]));
+    assert_eq!(lookup(&index, Filter::eq("", 5)), vec![2]);
+}
+
+#[test]
+fn search_with_indexed_filter() {
+    let collection = create_indexed_collection();
+    let query = Vector::random(DIMENSION);
+
+    // The filter is selective enough to scan the candidates
+    // so the result should be the true nearest neighbors.
+    let filter = Filter::eq("tenant", "tenant-7");
+    let result = collection.search_with_filter(&query, 5, &filter).unwrap();
+
+    let mut truth = collection.true_search(&query, 1000).unwrap();
+    truth.retain(|r| filter.matches(&r.data));
+    truth.truncate(5);
+
+    let ids: Vec<u32> = result.iter().map(|r| r.id).collect();
+    let true_ids: Vec<u32> = truth.iter().map(|r| r.id).collect();
+    assert_eq!(ids, true_ids);
+}
+
+#[test]
+fn index_updates_with_records() {
+    let mut collection = create_indexed_collection();
+    let query = Vector::random(DIMENSION);
+    let filter = Filter::eq("tenant", "new");
+
+    // Move a record to a new tenant.
+    let data = HashMap::from([("tenant", "new")]);
+    let record = Record::new(&Vector::random(DIMENSION), &data.into());
+    collection.update(&VectorID(0), &record).unwrap();
+
+    let result = collection.search_with_filter(&query, 5, &filter).unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].id, 0);
+
+    // Deleted records should be removed from the index.
+    collection.delete(&VectorID(0)).unwrap();
+    let result = collection.search_with_filter(&query, 5, &filter).unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn delete_index() {
+    let mut collection = create_indexed_collection();
+    assert!(collection.indexes().contains_key("tenant"));
+
+    collection.delete_index("tenant").unwrap();
+    assert!(collection.indexes().is_empty());
+    assert!(collection.delete_index("tenant").is_err());
+}
+
+#[test]
+fn index_lookup_keyword_compare() {
+    let mut index = FieldIndex::new(IndexType::Keyword);
+    let values: Vec<Metadata> = vec!["a".into(), "b".into(), "c".into()];
+
+    for (i, value) in values.iter().enumerate() {
+        index.insert(&i.into(), Some(value));
+    }
+
+    assert_eq!(lookup(&index, Filter::Gt("".into(), "b".into())), vec![2]);
+    assert_eq!(lookup(&index, Filter::Gte("".into(), "b".into())), vec![1, 2]);
+    assert_eq!(lookup(&index, Filter::Lt("".into(), "b".into())), vec![0]);
+    assert_eq!(lookup(&index, Filter::Lte("".into(), "b".into())), vec![0, 1]);
+}
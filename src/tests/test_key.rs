@@ -0,0 +1,178 @@
+use super::*;
+
+fn create_keyed_collection() -> Collection {
+    let records: Vec<Record> = Record::many_random(DIMENSION, LEN)
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| record.with_key(format!("doc-{i}")))
+        .collect();
+
+    Collection::build(&Config::default(), &records).unwrap()
+}
+
+#[test]
+fn get_by_key() {
+    let mut collection = create_keyed_collection();
+    let record = Record::random(DIMENSION).with_key(42);
+    collection.insert(&record).unwrap();
+
+    let key = RecordKey::from(42);
+    let stored = collection.get_by_key(&key).unwrap();
+    assert_eq!(stored.data, record.data);
+    assert_eq!(stored.key, Some(key));
+
+    let stored = collection.get_by_key(&"doc-7".into()).unwrap();
+    assert_eq!(stored.vector, collection.get(&VectorID(7)).unwrap().vector);
+}
+
+#[test]
+fn insert_duplicate_key() {
+    let mut collection = create_keyed_collection();
+    let record = Record::random(DIMENSION).with_key("doc-1");
+    assert!(collection.insert(&record).is_err());
+
+    // Records of the same batch can't share a key either.
+    let records = vec![Record::random(DIMENSION).with_key("new"); 2];
+    assert!(collection.insert_many(&records).is_err());
+    assert_eq!(collection.len(), LEN);
+}
+
+#[test]
+fn delete_by_key() {
+    let mut collection = create_keyed_collection();
+    let key = RecordKey::from("doc-3");
+    collection.delete_by_key(&key).unwrap();
+
+    assert!(!collection.contains(&VectorID(3)));
+    assert!(collection.get_by_key(&key).is_err());
+    assert!(collection.delete_by_key(&key).is_err());
+}
+
+#[test]
+fn upsert() {
+    let mut collection = create_keyed_collection();
+
+    // Upserting an existing key updates the record in place.
+    let record = Record::random(DIMENSION).with_key("doc-5");
+    let result = collection.upsert(&record).unwrap();
+    assert_eq!(result, UpsertResult { id: VectorID(5), inserted: false });
+    assert_eq!(collection.len(), LEN);
+    assert_eq!(collection.get(&result.id).unwrap().data, record.data);
+
+    // Upserting a new key inserts a new record.
+    let record = Record::random(DIMENSION).with_key("doc-new");
+    let result = collection.upsert(&record).unwrap();
+    assert_eq!(result.id, VectorID::from(LEN));
+    assert!(result.inserted);
+    assert_eq!(collection.len(), LEN + 1);
+
+    // Records without a key can't be upserted.
+    assert!(collection.upsert(&Record::random(DIMENSION)).is_err());
+}
+
+#[test]
+fn upsert_many() {
+    let mut collection = create_keyed_collection();
+    let records = vec![
+        Record::random(DIMENSION).with_key("doc-0"),
+        Record::random(DIMENSION).with_key("new-0"),
+        Record::random(DIMENSION).with_key("doc-1"),
+        Record::random(DIMENSION).with_key("new-1"),
+    ];
+
+    let results = collection.upsert_many(&records).unwrap();
+    let ids: Vec<u32> = results.iter().map(|r| r.id.0).collect();
+    let inserted: Vec<bool> = results.iter().map(|r| r.inserted).collect();
+    assert_eq!(ids, [0, LEN as u32, 1, LEN as u32 + 1]);
+    assert_eq!(inserted, [false, true, false, true]);
+    assert_eq!(collection.len(), LEN + 2);
+
+    // Re-ingesting the same records doesn't create duplicates.
+    let results = collection.upsert_many(&records).unwrap();
+    assert!(results.iter().all(|r| !r.inserted));
+    assert_eq!(collection.len(), LEN + 2);
+
+    // Keys must be present and unique within the batch.
+    let record = Record::random(DIMENSION);
+    let error = collection.upsert_many(&[record]).unwrap_err();
+    assert_eq!(error.message(), Error::key_required().message());
+
+    let duplicates = [records[1].clone(), records[1].clone()];
+    let error = collection.upsert_many(&duplicates).unwrap_err();
+    assert_eq!(error.message(), Error::key_exists().message());
+
+    // All records are linked in the graph with the new vectors.
+    for (result, record) in results.iter().zip(records.iter()) {
+        let nearest = collection.search(&record.vector, 1).unwrap();
+        assert_eq!(nearest[0].id, result.id.0);
+    }
+}
+
+#[test]
+fn upsert_many_invalid() {
+    let mut collection = create_keyed_collection();
+
+    // The records must have unique keys.
+    let records = vec![Record::random(DIMENSION).with_key("doc-0"); 2];
+    assert!(collection.upsert_many(&records).is_err());
+
+    let records = [Record::random(DIMENSION)];
+    assert!(collection.upsert_many(&records).is_err());
+
+    // Nothing is changed when a record is invalid.
+    let records = [
+        Record::random(DIMENSION).with_key("new"),
+        Record::random(DIMENSION + 1).with_key("doc-0"),
+    ];
+
+    assert!(collection.upsert_many(&records).is_err());
+    assert_eq!(collection.len(), LEN);
+    assert!(collection.get_by_key(&"new".into()).is_err());
+}
+
+#[test]
+fn update_keeps_key() {
+    let mut collection = create_keyed_collection();
+    collection.update(&VectorID(2), &Record::random(DIMENSION)).unwrap();
+    assert_eq!(collection.get(&VectorID(2)).unwrap().key, Some("doc-2".into()));
+
+    // The key of another record can't be taken.
+    let record = Record::random(DIMENSION).with_key("doc-3");
+    assert!(collection.update(&VectorID(2), &record).is_err());
+}
+
+#[test]
+fn search_returns_key() {
+    let collection = create_keyed_collection();
+    let record = collection.get(&VectorID(9)).unwrap();
+    let result = collection.search(&record.vector, 1).unwrap();
+    assert_eq!(result[0].key, Some("doc-9".into()));
+}
+
+#[test]
+fn keys_persist_in_database() {
+    let mut db = Database::new("data/012").unwrap();
+    let collection = create_keyed_collection();
+    db.save_collection(NAME, &collection).unwrap();
+
+    let handle = db.collection(NAME).unwrap();
+    handle.delete_by_key(&"doc-0".into()).unwrap();
+    let record = Record::random(DIMENSION).with_key(7);
+    handle.upsert(&record).unwrap();
+
+    let saved = db.get_collection(NAME).unwrap();
+    assert!(saved.get_by_key(&"doc-0".into()).is_err());
+    assert_eq!(saved.get_by_key(&7.into()).unwrap().data, record.data);
+    assert_eq!(saved.len(), LEN);
+}
+
+#[test]
+fn compact_keeps_keys() {
+    let mut collection = create_keyed_collection();
+    collection.delete(&VectorID(0)).unwrap();
+
+    let mapping = collection.compact(true).unwrap();
+    let record = collection.get_by_key(&"doc-10".into()).unwrap();
+    let id = mapping[&VectorID(10)];
+    assert_eq!(collection.get(&id).unwrap().vector, record.vector);
+}
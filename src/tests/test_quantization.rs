@@ -0,0 +1,357 @@
+use super::*;
+
+fn create_quantized_collection(kind: Quantization, rerank: bool) -> Collection {
+    let records = Record::many_random(DIMENSION, LEN * 5);
+    let config =
+        Config { quantization: Some(kind), rerank, ..Default::default() };
+    Collection::build(&config, &records).unwrap()
+}
+
+#[test]
+fn encode_decode() {
+    let vectors = [Vector::random(DIMENSION), Vector::random(DIMENSION)];
+    for kind in [Quantization::Scalar, Quantization::ScalarPerVector] {
+        let mut quantizer = ScalarQuantizer::new(kind);
+        quantizer.fit(vectors.iter());
+
+        for vector in vectors.iter() {
+            let code = quantizer.encode(vector);
+            let decoded: Vec<f32> = quantizer.decode(&code).collect();
+
+            // The values are rounded to the nearest level of
+            // the scales which are widened by the margin.
+            let diff = vector.0.iter().zip(decoded.iter());
+            assert!(diff.map(|(a, b)| (a - b).abs()).all(|d| d <= 0.003));
+        }
+    }
+}
+
+#[test]
+fn fit_fixes_scales() {
+    let mut quantizer = ScalarQuantizer::new(Quantization::Scalar);
+    assert!(!quantizer.is_fitted());
+    let vectors = [Vector::from(vec![0.0, 0.0]), Vector::from(vec![1.0, 1.0])];
+    quantizer.fit(vectors.iter());
+    let fitted = quantizer.scales.clone();
+
+    // The scales are not fitted again and the values out of
+    // them are clamped to the lowest or highest level.
+    let vector = Vector::from(vec![2.0, -1.0]);
+    quantizer.fit(std::iter::once(&vector));
+    assert_eq!(quantizer.scales, fitted);
+    assert_eq!(quantizer.encode(&vector).values, vec![255, 0]);
+}
+
+#[test]
+fn scalar_distance_table() {
+    let vectors = Vec::from_iter((0..10).map(|_| Vector::random(DIMENSION)));
+    let query = Vector::random(DIMENSION);
+    let distances =
+        [Distance::Dot, Distance::Euclidean, Distance::SquaredEuclidean];
+
+    for kind in [Quantization::Scalar, Quantization::ScalarPerVector] {
+        let mut quantizer = ScalarQuantizer::new(kind);
+        quantizer.fit(vectors.iter());
+
+        // The distances on the levels match the decoded vectors.
+        for distance in distances.iter() {
+            let table = quantizer.table(distance, &query).unwrap();
+            for vector in vectors.iter() {
+                let code = quantizer.encode(vector);
+                let decoded = quantizer.decode(&code);
+                let expected = distance.calculate_iter(&query, decoded);
+                let value = quantizer.distance(&table, &code);
+                assert!((value - expected).abs() < 1e-3, "{distance:?}");
+            }
+        }
+
+        assert!(quantizer.table(&Distance::Manhattan, &query).is_none());
+    }
+}
+
+#[test]
+fn search_quantized() {
+    for kind in [Quantization::Scalar, Quantization::ScalarPerVector] {
+        let collection = create_quantized_collection(kind, false);
+
+        // Only the quantized vectors are kept in memory.
+        assert!(collection.vectors.floats.is_empty());
+
+        // The nearest vector of a stored vector is itself.
+        for i in 0..20 {
+            let id = VectorID(i * 10);
+            let vector = collection.get(&id).unwrap().vector;
+            let result = collection.search(&vector, 1).unwrap();
+            assert_eq!(result[0].id, id.0);
+        }
+    }
+}
+
+#[test]
+fn search_rerank() {
+    let collection = create_quantized_collection(Quantization::Scalar, true);
+    let distance = &collection.config.distance;
+
+    // The results have the distances of the full precision vectors.
+    let query = Vector::random(DIMENSION);
+    let results = collection.search(&query, 10).unwrap();
+    for result in results.iter() {
+        let vector = &collection[&VectorID(result.id)];
+        assert_eq!(result.distance, distance.calculate(&query, vector));
+    }
+
+    let is_sorted = results.windows(2).all(|w| w[0].distance <= w[1].distance);
+    assert!(is_sorted);
+
+    // The true search is exact with the full precision vectors.
+    let true_results = collection.true_search(&query, 10).unwrap();
+    let expected = collection.true_search(&query, 1).unwrap();
+    assert_eq!(true_results[0].id, expected[0].id);
+}
+
+#[test]
+fn insert_quantized() {
+    let config = Config {
+        quantization: Some(Quantization::Scalar),
+        ..Default::default()
+    };
+
+    // The vectors are stored in full precision until enough
+    // of them are inserted to fit the scales.
+    let records = Record::many_random(DIMENSION, FIT_SAMPLE + LEN);
+    let (first, rest) = records.split_at(FIT_SAMPLE - 1);
+    let mut collection = Collection::new(&config);
+    for record in first.iter() {
+        collection.insert(record).unwrap();
+    }
+
+    assert!(collection.vectors.quantized().is_none());
+    for record in rest.iter() {
+        collection.insert(record).unwrap();
+    }
+
+    assert!(collection.vectors.quantized().is_some());
+    assert!(collection.vectors.floats.is_empty());
+    for (i, record) in records.iter().enumerate() {
+        let vector = collection.get(&i.into()).unwrap().vector;
+        let diff = record.vector.0.iter().zip(vector.0.iter());
+        assert!(diff.map(|(a, b)| (a - b).abs()).all(|d| d <= 0.003));
+    }
+}
+
+#[test]
+fn train_scalar() {
+    let config = Config {
+        quantization: Some(Quantization::Scalar),
+        ..Default::default()
+    };
+
+    // Training fits the scales to fewer vectors than the sample.
+    let mut collection = Collection::new(&config);
+    for record in Record::many_random(DIMENSION, LEN).iter() {
+        collection.insert(record).unwrap();
+    }
+
+    assert!(collection.vectors.quantized().is_none());
+    collection.train().unwrap();
+    assert!(collection.vectors.quantized().is_some());
+    assert_eq!(collection.vectors.codes.len(), LEN);
+}
+
+#[test]
+fn save_quantized() {
+    let mut db = Database::new("data/013").unwrap();
+    let mut collection =
+        create_quantized_collection(Quantization::Scalar, false);
+    db.save_collection(NAME, &collection).unwrap();
+
+    // Inserting a vector out of the scales changes all records.
+    let vector = Vector::from(vec![2.0; DIMENSION]);
+    collection.insert(&Record::new(&vector, &0.into())).unwrap();
+    db.save_collection(NAME, &collection).unwrap();
+
+    let saved = db.get_collection(NAME).unwrap();
+    assert!(saved.vectors.floats.is_empty());
+    assert_eq!(saved.vectors.quantizer, collection.vectors.quantizer);
+    assert_eq!(saved.list().unwrap().len(), collection.len());
+
+    let query = Vector::random(DIMENSION);
+    let ids = |c: &Collection| -> Vec<u32> {
+        c.search(&query, 10).unwrap().iter().map(|r| r.id).collect()
+    };
+
+    assert_eq!(ids(&saved), ids(&collection));
+}
+
+#[test]
+fn compact_quantized() {
+    let mut collection =
+        create_quantized_collection(Quantization::Scalar, false);
+    for i in 0..LEN {
+        collection.delete(&i.into()).unwrap();
+    }
+
+    // The stored codes are kept instead of quantizing
+    // the decoded vectors again.
+    let code = collection.vectors.codes[&LEN.into()].clone();
+    collection.compact(true).unwrap();
+    assert_eq!(collection.vectors.len(), LEN * 4);
+    assert!(collection.vectors.floats.is_empty());
+    assert_eq!(collection.vectors.codes[&VectorID(0)], code);
+}
+
+#[test]
+fn product_distance_table() {
+    let vectors: Vec<Vector> =
+        (0..LEN * 5).map(|_| Vector::random(DIMENSION)).collect();
+    let refs: Vec<&Vector> = vectors.iter().collect();
+
+    let mut quantizer = ProductQuantizer::new(16);
+    quantizer.train(&refs).unwrap();
+
+    // The looked up distances match the decoded vector distances.
+    let query = Vector::random(DIMENSION);
+    let distances = [
+        Distance::Dot,
+        Distance::Euclidean,
+        Distance::Cosine,
+        Distance::Hamming,
+        Distance::Manhattan,
+        Distance::Chebyshev,
+        Distance::SquaredEuclidean,
+    ];
+
+    for distance in distances {
+        let table = quantizer.table(&distance, &query).unwrap();
+        for vector in vectors.iter().take(10) {
+            let code = quantizer.encode(vector);
+            let expected = distance.calculate(&query, &quantizer.decode(&code));
+            let found = quantizer.distance(&table, &code);
+            assert!((expected - found).abs() <= expected.abs() * 1e-4);
+        }
+    }
+
+    // The Jaccard distance is calculated on the decoded vectors.
+    assert!(quantizer.table(&Distance::Jaccard, &query).is_none());
+}
+
+#[test]
+fn search_product() {
+    let collection = create_quantized_collection(Quantization::Product, false);
+    assert!(collection.vectors.floats.is_empty());
+    assert_eq!(collection.vectors.codes[&VectorID(0)].values.len(), 8);
+
+    for i in 0..20 {
+        let id = VectorID(i * 10);
+        let vector = collection.get(&id).unwrap().vector;
+        let result = collection.search(&vector, 1).unwrap();
+        assert_eq!(result[0].id, id.0);
+    }
+}
+
+#[test]
+fn train_product() {
+    let config = Config {
+        quantization: Some(Quantization::Product),
+        ..Default::default()
+    };
+
+    // The vectors are stored in full precision until trained.
+    let mut collection = Collection::new(&config);
+    collection.insert_many(&Record::many_random(DIMENSION, LEN)).unwrap();
+    assert_eq!(collection.vectors.floats.len(), LEN);
+    assert!(collection.vectors.codes.is_empty());
+
+    collection.train().unwrap();
+    assert!(collection.vectors.floats.is_empty());
+    assert_eq!(collection.vectors.codes.len(), LEN);
+
+    // New records are quantized with the trained codebooks.
+    let record = Record::random(DIMENSION);
+    collection.insert(&record).unwrap();
+    assert_eq!(collection.vectors.codes.len(), LEN + 1);
+
+    let vector = collection.get(&VectorID::from(LEN)).unwrap().vector;
+    let result = collection.search(&vector, 1).unwrap();
+    assert_eq!(result[0].id, LEN as u32);
+}
+
+#[test]
+fn save_product() {
+    let mut db = Database::new("data/014").unwrap();
+    let collection = create_quantized_collection(Quantization::Product, true);
+    db.save_collection(NAME, &collection).unwrap();
+
+    let saved = db.get_collection(NAME).unwrap();
+    assert_eq!(saved.vectors.quantizer, collection.vectors.quantizer);
+    assert_eq!(saved.vectors.codes, collection.vectors.codes);
+    assert_eq!(saved.vectors.floats.len(), collection.len());
+
+    let query = Vector::random(DIMENSION);
+    let ids = |c: &Collection| -> Vec<u32> {
+        c.search(&query, 10).unwrap().iter().map(|r| r.id).collect()
+    };
+
+    assert_eq!(ids(&saved), ids(&collection));
+}
+
+#[test]
+fn encode_binary() {
+    let vector =
+        Vector::from(vec![1.0, -2.0, 0.0, 3.0, 0.5, -1.0, 2.0, -3.0, 4.0]);
+    let mut quantizer = BinaryQuantizer::default();
+    quantizer.fit(std::iter::once(&vector));
+
+    // The sign bits are packed into bytes from the lowest bit.
+    let code = quantizer.encode(&vector);
+    assert_eq!(code.values, vec![0b0101_1001, 0b0000_0001]);
+
+    let decoded: Vec<f32> = quantizer.decode(&code).collect();
+    let signs = [1.0, -1.0, -1.0, 1.0, 1.0, -1.0, 1.0, -1.0, 1.0];
+    assert_eq!(decoded, signs);
+
+    let other = quantizer.encode(&Vector::from(vec![1.0; 9]));
+    let distance =
+        Distance::Hamming.calculate(&vector, &Vector::from(vec![1.0; 9]));
+    assert_eq!(BinaryQuantizer::hamming(&code.values, &other.values), distance);
+}
+
+#[test]
+fn search_binary() {
+    // The random values are centered so the signs are mixed.
+    let records: Vec<Record> = (0..LEN * 5)
+        .map(|_| Vector::random(DIMENSION).0.iter().map(|x| x - 0.5).collect())
+        .map(|vector: Vector| Record::new(&vector, &0.into()))
+        .collect();
+
+    let config = Config {
+        distance: Distance::Hamming,
+        quantization: Some(Quantization::Binary),
+        ..Default::default()
+    };
+
+    let collection = Collection::build(&config, &records).unwrap();
+    assert!(collection.vectors.floats.is_empty());
+    assert_eq!(collection.vectors.codes[&VectorID(0)].values.len(), 16);
+
+    // The stored vector has no different bits from itself.
+    for i in 0..20 {
+        let vector = &records[i * 10].vector;
+        let result = collection.search(vector, 1).unwrap();
+        assert_eq!(result[0].distance, 0.0);
+    }
+}
+
+#[test]
+fn search_binary_rerank() {
+    let collection = create_quantized_collection(Quantization::Binary, true);
+    let distance = &collection.config.distance;
+
+    // The binary codes only select the candidates.
+    let query = Vector::random(DIMENSION);
+    let results = collection.search(&query, 10).unwrap();
+    for result in results.iter() {
+        let vector = &collection[&VectorID(result.id)];
+        assert_eq!(result.distance, distance.calculate(&query, vector));
+    }
+}
@@ -0,0 +1,31 @@
+use super::*;
+
+#[test]
+fn kernels_match_scalar() {
+    let kernels = Kernel::available();
+    assert_eq!(kernels[0].name(), "scalar");
+    assert_eq!(Kernel::detect().name(), kernels.last().unwrap().name());
+
+    // The lengths cover the values left over after the SIMD chunks.
+    for len in [0, 1, 3, 4, 7, 8, 15, 16, 17, 33, DIMENSION, 131] {
+        let a = Vector::random(len);
+        let b = Vector::random(len);
+
+        let scalar = kernels[0];
+        let close = |x: f32, y: f32| (x - y).abs() <= 1e-4 * y.abs().max(1.0);
+        for kernel in kernels.iter() {
+            let dot = kernel.dot(&a.0, &b.0);
+            assert!(close(dot, scalar.dot(&a.0, &b.0)), "{}", kernel.name());
+
+            let squared = kernel.squared(&a.0, &b.0);
+            let expected = scalar.squared(&a.0, &b.0);
+            assert!(close(squared, expected), "{}", kernel.name());
+
+            if len > 0 {
+                let cosine = kernel.cosine(&a.0, &b.0);
+                let expected = scalar.cosine(&a.0, &b.0);
+                assert!(close(cosine, expected), "{}", kernel.name());
+            }
+        }
+    }
+}
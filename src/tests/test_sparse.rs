@@ -0,0 +1,133 @@
+use super::*;
+
+/// Returns the sparse vector with a weight of 1 for each term.
+fn terms(indices: &[u32]) -> SparseVector {
+    SparseVector::new(indices.to_vec(), vec![1.0; indices.len()]).unwrap()
+}
+
+/// Creates a collection whose records have one of ten terms.
+/// Record 42 also has a term no other record has and its vector
+/// is far from the others so only the sparse search finds it.
+fn create_sparse_collection(config: &Config) -> Collection {
+    let far = Vector::from(vec![10.0; DIMENSION]);
+    let records: Vec<Record> = Record::many_random(DIMENSION, LEN)
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| match i {
+            42 => Record { vector: far.clone(), ..record }
+                .with_sparse(terms(&[i as u32 % 10, 100])),
+            _ => record.with_sparse(terms(&[i as u32 % 10])),
+        })
+        .collect();
+
+    Collection::build(config, &records).unwrap()
+}
+
+#[test]
+fn sparse_vector_new() {
+    let vector = SparseVector::new(vec![9, 2, 5], vec![0.9, 0.2, 0.5]);
+    let vector = vector.unwrap();
+    assert_eq!(vector.indices(), vec![2, 5, 9]);
+    assert_eq!(vector.values(), vec![0.2, 0.5, 0.9]);
+
+    let other = SparseVector::new(vec![5, 9, 11], vec![2.0, 1.0, 3.0]);
+    assert_eq!(vector.dot(&other.unwrap()), 0.5 * 2.0 + 0.9);
+
+    assert!(SparseVector::new(vec![1, 1], vec![0.1, 0.2]).is_err());
+    assert!(SparseVector::new(vec![1, 2], vec![0.1]).is_err());
+}
+
+#[test]
+fn sparse_search() {
+    let mut collection = create_sparse_collection(&Config::default());
+    let query = SparseVector::new(vec![3, 100], vec![1.0, 2.0]).unwrap();
+
+    // Record 42 is the only one with both terms.
+    let results = collection.sparse_search(&query, 5).unwrap();
+    assert_eq!(results[0].id, 42);
+    assert_eq!(results[0].distance, 2.0);
+    assert!(results[1..].iter().all(|r| r.id % 10 == 3));
+    assert!(results[1..].iter().all(|r| r.distance == 1.0));
+
+    // The sparse vector is replaced when the record is updated.
+    let record = Record::random(DIMENSION);
+    collection.update(&VectorID(42), &record).unwrap();
+    assert!(collection.get(&VectorID(42)).unwrap().sparse.is_none());
+    let results = collection.sparse_search(&terms(&[100]), 5).unwrap();
+    assert!(results.is_empty());
+}
+
+#[test]
+fn sparse_search_after_compact() {
+    let mut collection = create_sparse_collection(&Config::default());
+    for i in 0..10 {
+        collection.delete(&VectorID(i)).unwrap();
+    }
+
+    collection.compact(true).unwrap();
+    let results = collection.sparse_search(&terms(&[100]), 5).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, 32);
+
+    let record = collection.get(&VectorID(32)).unwrap();
+    assert_eq!(record.sparse, Some(terms(&[2, 100])));
+}
+
+#[test]
+fn hybrid_search_rrf() {
+    let collection = create_sparse_collection(&Config::default());
+    let vector = collection.get(&VectorID(0)).unwrap().vector;
+
+    // The top results of both searches share the first rank.
+    let fusion = Fusion::ReciprocalRank(60.0);
+    let results =
+        collection.hybrid_search(&vector, &terms(&[100]), 10, &fusion).unwrap();
+
+    assert_eq!(results[0].id, 0);
+    assert_eq!(results[1].id, 42);
+    assert_eq!(results[1].distance, 1.0 / 61.0);
+
+    // The dense search alone misses the keyword match.
+    let dense = collection.search(&vector, 2).unwrap();
+    assert!(dense.iter().all(|r| r.id != 42));
+}
+
+#[test]
+fn hybrid_search_weighted_sum() {
+    let config = Config { distance: Distance::Cosine, ..Default::default() };
+    let collection = create_sparse_collection(&config);
+    let vector = collection.get(&VectorID(0)).unwrap().vector;
+
+    let fusion = Fusion::WeightedSum(0.5);
+    let results =
+        collection.hybrid_search(&vector, &terms(&[100]), 10, &fusion).unwrap();
+
+    // The dense score is calculated for the sparse match too.
+    let other = collection.get(&VectorID(42)).unwrap().vector;
+    let similarity = Distance::Cosine.calculate(&vector, &other);
+    assert_eq!(results[0].id, 42);
+    assert!((results[0].distance - (0.5 * similarity + 0.5)).abs() < 1e-5);
+
+    let scores: Vec<f32> = results.iter().map(|r| r.distance).collect();
+    assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+}
+
+#[test]
+fn concurrent_hybrid_search() {
+    let collection = create_sparse_collection(&Config::default());
+    let concurrent = ConcurrentCollection::from(collection);
+
+    let record = Record::random(DIMENSION).with_sparse(terms(&[200]));
+    let id = concurrent.insert(&record).unwrap();
+    let results = concurrent.sparse_search(&terms(&[200]), 5).unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, id.0);
+
+    let fusion = Fusion::ReciprocalRank(60.0);
+    let results = concurrent
+        .hybrid_search(&record.vector, &terms(&[200]), 5, &fusion)
+        .unwrap();
+
+    assert_eq!(results[0].id, id.0);
+    assert_eq!(concurrent.snapshot().get(&id).unwrap().sparse, record.sparse);
+}